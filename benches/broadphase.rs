@@ -0,0 +1,94 @@
+//! Compares the old all-pairs enemy check against the spatial hash, per enemy, as the crowd grows.
+//! Masks are spread at a fixed density, like a wave packed around the player, so a flat cost per
+//! enemy means the broadphase scales linearly.
+//!
+//! Run with `cargo bench --bench broadphase`.
+#[path = "../src/bushido/broadphase.rs"]
+mod broadphase;
+
+use bevy::math::bounding::BoundingCircle;
+use bevy::math::bounding::IntersectsVolume;
+use bevy::prelude::*;
+use broadphase::SpatialHash;
+use broadphase::CELL_SIZE;
+use rand::Rng;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use std::hint::black_box;
+use std::time::Duration;
+use std::time::Instant;
+
+const COUNTS: [usize; 6] = [50, 200, 500, 1000, 2000, 4000];
+/// Floor space per mask, in units squared.
+const AREA_PER_MASK: f32 = 60.0 * 60.0;
+const MIN_TIME: Duration = Duration::from_millis(500);
+
+fn crowd(count: usize) -> Vec<(Entity, BoundingCircle)> {
+    let mut rng = Pcg32::seed_from_u64(count as u64);
+    let half_side = f32::sqrt(count as f32 * AREA_PER_MASK) / 2.0;
+    (0..count)
+        .map(|index| {
+            let center = Vec2::new(
+                rng.gen_range(-half_side..half_side),
+                rng.gen_range(-half_side..half_side),
+            );
+            (
+                Entity::from_raw(index as u32),
+                BoundingCircle::new(center, 15.0),
+            )
+        })
+        .collect()
+}
+
+fn all_pairs(crowd: &[(Entity, BoundingCircle)]) -> usize {
+    let mut found = 0;
+    for (index, (_, collider)) in crowd.iter().enumerate() {
+        for (_, other) in crowd[index + 1..].iter() {
+            if collider.center.distance(other.center) < CELL_SIZE || collider.intersects(other) {
+                found += 1;
+            }
+        }
+    }
+    found
+}
+
+fn hashed_pairs(hash: &mut SpatialHash, crowd: &[(Entity, BoundingCircle)]) -> usize {
+    hash.clear();
+    for (entity, collider) in crowd.iter() {
+        hash.insert(*entity, *collider);
+    }
+    hash.pairs(CELL_SIZE).len()
+}
+
+/// Average time per run, repeating until `MIN_TIME` has passed.
+fn time(mut run: impl FnMut() -> usize) -> Duration {
+    let start = Instant::now();
+    let mut runs = 0;
+    while start.elapsed() < MIN_TIME {
+        black_box(run());
+        runs += 1;
+    }
+    start.elapsed() / runs
+}
+
+fn main() {
+    println!(
+        "{:>6} {:>8} {:>18} {:>18}",
+        "masks", "pairs", "all pairs ns/mask", "hashed ns/mask"
+    );
+    let mut hash = SpatialHash::default();
+    for count in COUNTS {
+        let crowd = crowd(count);
+        let pairs = all_pairs(&crowd);
+        assert_eq!(pairs, hashed_pairs(&mut hash, &crowd));
+        let naive = time(|| all_pairs(&crowd));
+        let hashed = time(|| hashed_pairs(&mut hash, &crowd));
+        println!(
+            "{:>6} {:>8} {:>18} {:>18}",
+            count,
+            pairs,
+            naive.as_nanos() / count as u128,
+            hashed.as_nanos() / count as u128,
+        );
+    }
+}
@@ -1,34 +1,78 @@
 #![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+mod accessibility;
+mod affix;
+mod arena;
+mod assets;
+mod broadphase;
+mod collision;
+mod draft;
 mod enemy;
+mod locale;
 mod menu;
+mod music;
 mod particle;
+mod pickup;
 mod player;
-
+mod save;
+mod settings;
+mod sound;
+mod spatial;
+
+use crate::bushido::accessibility::AccessibilityPlugin;
+use crate::bushido::affix::AffixPlugin;
+use crate::bushido::arena::ArenaPlugin;
+use crate::bushido::assets::GameAssets;
+use crate::bushido::assets::GameAssetsPlugin;
+use crate::bushido::collision::collide_with_walls;
+use crate::bushido::draft::DraftPlugin;
 use crate::bushido::enemy::EnemyPlugin;
+use crate::bushido::locale::LocalePlugin;
 use crate::bushido::menu::MenuPlugin;
+use crate::bushido::music::MusicPlugin;
 use crate::bushido::particle::ParticlePlugin;
+use crate::bushido::pickup::PickupPlugin;
 use crate::bushido::player::PlayerAction;
 use crate::bushido::player::PlayerPlugin;
+use crate::bushido::save::SaveFile;
+use crate::bushido::settings::SettingsPlugin;
+use crate::bushido::sound::SoundId;
+use crate::bushido::sound::SoundPlugin;
+use crate::bushido::spatial::SpatialAudioPlugin;
 use crate::{set_up_windows, GameGlobal};
 use bevy::math::bounding::BoundingCircle;
 use bevy::prelude::*;
 use bevy::sprite::MaterialMesh2dBundle;
 use bevy::utils::Duration;
+use bevy::utils::HashMap;
 use bevy::winit::WinitWindows;
 use bevy::{input::gamepad::GamepadEvent, input::keyboard::KeyboardInput};
 use leafwing_input_manager::prelude::*;
-use rand::Rng;
 
 pub struct BushidoPlugin;
 
 impl Plugin for BushidoPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(PlayerPlugin)
+        let save = SaveFile::load();
+        app.add_plugins(GameAssetsPlugin)
+            .add_plugins(LocalePlugin)
+            .add_plugins(AccessibilityPlugin)
+            .add_plugins(PlayerPlugin)
             .add_plugins(MenuPlugin)
             .add_plugins(EnemyPlugin)
+            .add_plugins(AffixPlugin)
+            .add_plugins(PickupPlugin)
+            .add_plugins(DraftPlugin)
+            .add_plugins(SettingsPlugin)
+            .add_plugins(ArenaPlugin)
+            .add_plugins(SoundPlugin)
+            .add_plugins(SpatialAudioPlugin)
+            .add_plugins(MusicPlugin)
             // .add_plugins(ParticlePlugin)
             // .init_state::<ActiveInput>()
             .init_state::<GameState>()
+            .add_sub_state::<PlayPhase>()
+            .insert_resource(Stats::load(&save))
+            .insert_resource(save)
             .add_systems(
                 Update,
                 (
@@ -48,13 +92,14 @@ impl Plugin for BushidoPlugin {
                 Update,
                 (
                     animate_sprites,
-                    walls,
-                    play_sounds,
-                    update_colliders.run_if(in_state(GameState::Play)),
+                    collide_with_walls,
+                    update_colliders.run_if(in_state(PlayPhase::Fighting)),
                     advance_menu.run_if(in_state(GameState::Menu)),
+                    player_died.run_if(in_state(GameState::Play)),
                 ),
             )
-            .add_event::<Sound>();
+            .add_event::<Sound>()
+            .add_event::<PlayerDied>();
     }
 }
 
@@ -67,7 +112,11 @@ enum ActiveInput {
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum GameState {
+    /// Waits on `GameAssets` before showing anything.
     #[default]
+    Loading,
+    /// Asks about reduced flashing on the first launch, before anything bright is on screen.
+    Prompt,
     Menu,
     Fadeout,
     Play,
@@ -75,6 +124,95 @@ pub enum GameState {
     DarkPresenceAttack,
 }
 
+/// Splits `Play` into the fight itself and the breaks between waves, so pausing for a draft does
+/// not run the enter and exit hooks of `Play`.
+#[derive(SubStates, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+#[source(GameState = GameState::Play)]
+pub enum PlayPhase {
+    #[default]
+    Fighting,
+    Draft,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DeathCause {
+    Wounds,
+    DarkPresence,
+}
+
+impl DeathCause {
+    pub const ALL: [DeathCause; 2] = [DeathCause::Wounds, DeathCause::DarkPresence];
+
+    /// Where the number of runs it ended is kept in the `SaveFile`.
+    fn save_key(&self) -> &'static str {
+        match self {
+            DeathCause::Wounds => "deaths_wounds",
+            DeathCause::DarkPresence => "deaths_dark_presence",
+        }
+    }
+}
+
+/// Every way a run can end goes through this event.
+#[derive(Event)]
+pub struct PlayerDied {
+    pub cause: DeathCause,
+}
+
+/// Kept in the `SaveFile` so they carry across launches.
+#[derive(Resource, Default)]
+pub struct Stats {
+    pub runs: usize,
+    pub best_kills: usize,
+    /// How many runs each cause has ended.
+    pub deaths: HashMap<DeathCause, usize>,
+}
+
+impl Stats {
+    fn load(save: &SaveFile) -> Stats {
+        Stats {
+            runs: save.get("runs").unwrap_or(0),
+            best_kills: save.get("best_kills").unwrap_or(0),
+            deaths: DeathCause::ALL
+                .iter()
+                .map(|cause| (*cause, save.get(cause.save_key()).unwrap_or(0)))
+                .collect(),
+        }
+    }
+}
+
+/// Records the death and submits the score to the save file, then hands over to the effect for
+/// that cause. Every cause ends up in `GameOver`, and cleanup for all of them happens when it's
+/// left.
+fn player_died(
+    global: Res<GameGlobal>,
+    mut stats: ResMut<Stats>,
+    mut save: ResMut<SaveFile>,
+    mut died: EventReader<PlayerDied>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(cause) = died.read().next().map(|death| death.cause) else {
+        return;
+    };
+    died.clear();
+
+    stats.runs += 1;
+    stats.best_kills = usize::max(stats.best_kills, global.kills);
+    *stats.deaths.entry(cause).or_insert(0) += 1;
+    save.set("runs", stats.runs);
+    save.set("best_kills", stats.best_kills);
+    save.set(cause.save_key(), stats.deaths[&cause]);
+    save.write();
+    info!(
+        "Run over: {} kills to {:?} (best {})",
+        global.kills, cause, stats.best_kills
+    );
+
+    match cause {
+        DeathCause::Wounds => next_state.set(GameState::GameOver),
+        DeathCause::DarkPresence => next_state.set(GameState::DarkPresenceAttack),
+    }
+}
+
 fn advance_menu(
     mut next_state: ResMut<NextState<GameState>>,
     action_state: Res<ActionState<PlayerAction>>,
@@ -227,7 +365,7 @@ fn fadeout_update(
                 next_state.set(GameState::Menu);
             }
         }
-        GameState::Menu => {
+        GameState::Prompt | GameState::Menu => {
             if global.fadeout > 0.0 {
                 material.color = Color::srgba(0.0, 0.0, 0.0, global.fadeout);
                 global.fadeout -= f32::min(time.delta_seconds() * 2.0, global.fadeout);
@@ -238,6 +376,9 @@ fn fadeout_update(
         GameState::DarkPresenceAttack => {
             material.color = Color::srgba(0.0, 0.0, 0.0, 0.0);
         }
+        GameState::Loading => {
+            material.color = Color::srgba(0.0, 0.0, 0.0, global.fadeout);
+        }
     }
 }
 
@@ -266,6 +407,8 @@ struct Physical {
     collider: BoundingCircle,
     wall_padding: f32,
     hit_cooldown: Timer,
+    /// Pushed apart from other enemies it overlaps, see `enemy_collisions`.
+    separates: bool,
 }
 
 impl Default for Physical {
@@ -279,6 +422,7 @@ impl Default for Physical {
             collider: BoundingCircle::new(Vec2::ZERO, 15.0),
             wall_padding: 6.0,
             hit_cooldown: Timer::new(Duration::from_secs_f32(1.0), TimerMode::Once),
+            separates: true,
         }
     }
 }
@@ -326,96 +470,13 @@ struct Animate {
     current: usize,
 }
 
-fn walls(global: Res<GameGlobal>, mut things: Query<(&mut Transform, &mut Physical)>) {
-    for (mut transform, mut physical) in things.iter_mut() {
-        //inner walls
-        if f32::abs(f32::abs(transform.translation.x) - global.inner_world_size.x / 2.0)
-            < physical.collider.radius() + physical.wall_padding
-            && f32::abs(transform.translation.y) < global.inner_world_size.y / 2.0
-        {
-            transform.translation.x =
-                f32::signum(transform.translation.x) * global.inner_world_size.x / 2.0
-                    + f32::signum(transform.translation.x)
-                        * f32::signum(
-                            f32::abs(transform.translation.x) - global.inner_world_size.x / 2.0,
-                        )
-                        * (physical.collider.radius() + physical.wall_padding);
-            physical.velocity.x = 0.0;
-        }
-
-        if f32::abs(f32::abs(transform.translation.y) - global.inner_world_size.y / 2.0)
-            < physical.collider.radius() + physical.wall_padding
-            && f32::abs(transform.translation.x) < global.inner_world_size.x / 2.0
-        {
-            transform.translation.y =
-                f32::signum(transform.translation.y) * global.inner_world_size.y / 2.0
-                    + f32::signum(transform.translation.y)
-                        * f32::signum(
-                            f32::abs(transform.translation.y) - global.inner_world_size.y / 2.0,
-                        )
-                        * (physical.collider.radius() + physical.wall_padding);
-            physical.velocity.y = 0.0;
-        }
-
-        let x_bound;
-        let y_bound;
-
-        if global.expanded {
-            x_bound = global.inner_world_size.x * 2.0 / 3.0;
-            y_bound = global.inner_world_size.y * 2.0 / 3.0;
-        } else {
-            x_bound = global.inner_world_size.x / 2.0;
-            y_bound = global.inner_world_size.y / 2.0;
-        }
-
-        //outer walls
-        if f32::abs(transform.translation.x)
-            > x_bound - (physical.collider.radius() + physical.wall_padding)
-        {
-            transform.translation.x = f32::signum(transform.translation.x)
-                * (x_bound - (physical.collider.radius() + physical.wall_padding));
-            physical.velocity.x = 0.0;
-        }
-        if f32::abs(transform.translation.y)
-            > y_bound - (physical.collider.radius() + physical.wall_padding)
-        {
-            transform.translation.y = f32::signum(transform.translation.y)
-                * (y_bound - (physical.collider.radius() + physical.wall_padding));
-            physical.velocity.y = 0.0;
-        }
-    }
-}
-
 #[derive(Event)]
 struct Sound {
-    name: String,
+    id: SoundId,
     position: Vec3,
     speed: f32,
 }
 
-fn play_sounds(
-    mut global: ResMut<GameGlobal>,
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut new_sounds: EventReader<Sound>,
-) {
-    for sound in new_sounds.read() {
-        let sound_handle = asset_server.load(format!("embedded://{}.mp3", sound.name));
-        commands.spawn((
-            SpatialBundle {
-                transform: Transform::from_xyz(sound.position.x, sound.position.y, 0.0),
-                ..default()
-            },
-            AudioBundle {
-                source: sound_handle.clone(),
-                settings: PlaybackSettings::DESPAWN
-                    .with_spatial(true)
-                    .with_speed(sound.speed - 0.1 + global.rand.gen::<f32>() * 0.2),
-            },
-        ));
-    }
-}
-
 fn animate_sprites(
     time: Res<Time>,
     mut sprites: Query<(&mut TextureAtlas, &mut Transform, &mut Animate), With<Animate>>,
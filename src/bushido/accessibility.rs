@@ -0,0 +1,160 @@
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use crate::bushido::assets::GameAssets;
+use crate::bushido::locale::Localized;
+use crate::bushido::locale::Localizer;
+use crate::bushido::player::PlayerAction;
+use crate::bushido::save::SaveFile;
+use crate::bushido::settings::Settings;
+use crate::bushido::sound::SoundId;
+use crate::bushido::ActionState;
+use crate::bushido::GameState;
+use crate::bushido::Sound;
+use crate::MainCamera;
+use bevy::core_pipeline::bloom::BloomSettings;
+use bevy::prelude::*;
+
+/// Bloom intensity with reduced flashing, down from `BloomSettings::NATURAL`'s 0.15.
+pub const REDUCED_BLOOM_INTENSITY: f32 = 0.05;
+/// How long the Dark Presence attack takes to fade in with reduced flashing, in seconds.
+pub const REDUCED_ATTACK_FADE: f32 = 2.0;
+/// How bright the hit count gets with reduced flashing, down from 0.5.
+pub const REDUCED_HITCOUNT_ALPHA: f32 = 0.2;
+/// With reduced flashing the hit count lasts this many times longer, so it rises and falls slowly.
+pub const REDUCED_HITCOUNT_STRETCH: f32 = 2.0;
+/// The share of that time it spends fading in rather than appearing at once.
+pub const REDUCED_HITCOUNT_RISE: f32 = 0.25;
+/// Set in the `SaveFile` once the launch prompt has been answered, so it's only asked once.
+pub const PROMPT_ANSWERED: &str = "prompt_answered";
+const REDUCED_FLASHING: &str = "reduced_flashing";
+
+pub struct AccessibilityPlugin;
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, load_reduced_flashing)
+            .add_systems(OnEnter(GameState::Prompt), show_prompt)
+            .add_systems(OnExit(GameState::Prompt), hide_prompt)
+            .add_systems(
+                Update,
+                (answer_prompt, update_prompt.after(answer_prompt))
+                    .run_if(in_state(GameState::Prompt)),
+            )
+            .add_systems(Update, (apply_bloom, save_reduced_flashing));
+    }
+}
+
+#[derive(Component)]
+struct Prompt;
+
+#[derive(Component)]
+struct PromptChoice;
+
+/// Asks about reduced flashing before the title screen, where nothing bright has happened yet.
+fn show_prompt(mut commands: Commands, assets: Res<GameAssets>) {
+    let lines = [
+        (Some("prompt-flashing"), 120.0, 45.0, 1.0),
+        (None, 20.0, 35.0, 1.0),
+        (Some("prompt-hint"), -60.0, 25.0, 0.5),
+    ];
+    for (key, y, font_size, alpha) in lines {
+        let mut prompt = commands.spawn((
+            Prompt,
+            Text2dBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font: assets.font.clone(),
+                        font_size,
+                        color: Color::srgba(2.5, 0.25, 0.25, alpha),
+                        ..default()
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                transform: Transform::from_xyz(0.0, y, 10.0),
+                ..default()
+            },
+        ));
+        match key {
+            Some(key) => prompt.insert(Localized { key }),
+            None => prompt.insert(PromptChoice),
+        };
+    }
+}
+
+fn hide_prompt(mut commands: Commands, prompt: Query<Entity, With<Prompt>>) {
+    for entity in prompt.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// `Dodge` flips reduced flashing, the same as in the settings, and `Slash` goes on to the title
+/// for good.
+fn answer_prompt(
+    action_state: Res<ActionState<PlayerAction>>,
+    mut settings: ResMut<Settings>,
+    mut save: ResMut<SaveFile>,
+    mut sound: EventWriter<Sound>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if action_state.just_pressed(&PlayerAction::Dodge) {
+        settings.reduced_flashing = !settings.reduced_flashing;
+        sound.send(Sound {
+            id: SoundId::Select,
+            position: Vec3::ZERO,
+            speed: 1.0,
+        });
+    }
+    if action_state.just_pressed(&PlayerAction::Slash) {
+        save.set(PROMPT_ANSWERED, true);
+        save.write();
+        next_state.set(GameState::Menu);
+    }
+}
+
+fn update_prompt(
+    settings: Res<Settings>,
+    localizer: Localizer,
+    mut choice: Query<&mut Text, With<PromptChoice>>,
+) {
+    let value = localizer.get(if settings.reduced_flashing {
+        "on"
+    } else {
+        "off"
+    });
+    let label = localizer.format("setting-reduced-flashing", &[("value", value)]);
+    for mut text in choice.iter_mut() {
+        localizer.set(&mut text, label.clone());
+    }
+}
+
+fn apply_bloom(settings: Res<Settings>, mut cameras: Query<&mut BloomSettings, With<MainCamera>>) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut bloom in cameras.iter_mut() {
+        *bloom = if settings.reduced_flashing {
+            BloomSettings {
+                intensity: REDUCED_BLOOM_INTENSITY,
+                ..BloomSettings::NATURAL
+            }
+        } else {
+            BloomSettings::NATURAL
+        };
+    }
+}
+
+fn load_reduced_flashing(save: Res<SaveFile>, mut settings: ResMut<Settings>) {
+    if let Some(reduced_flashing) = save.get(REDUCED_FLASHING) {
+        settings.reduced_flashing = reduced_flashing;
+    }
+}
+
+/// Writes the choice out whenever it differs from the saved one, from the prompt or the settings.
+fn save_reduced_flashing(settings: Res<Settings>, mut save: ResMut<SaveFile>) {
+    if !settings.is_changed() {
+        return;
+    }
+    if save.get(REDUCED_FLASHING) != Some(settings.reduced_flashing) {
+        save.set(REDUCED_FLASHING, settings.reduced_flashing);
+        save.write();
+    }
+}
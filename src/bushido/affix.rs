@@ -0,0 +1,298 @@
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use crate::bushido::collision::Wall;
+use crate::bushido::collision::WallKind;
+use crate::bushido::enemy::finish_him;
+use crate::bushido::enemy::Enemy;
+use crate::bushido::enemy::EnemyHit;
+use crate::bushido::enemy::EnemySprite;
+use crate::bushido::player::Finish;
+use crate::bushido::player::Player;
+use crate::bushido::player::PlayerCooldowns;
+use crate::bushido::player::PlayerHit;
+use crate::bushido::sound::SoundId;
+use crate::bushido::GameState;
+use crate::bushido::Physical;
+use crate::bushido::PlayPhase;
+use crate::bushido::Sound;
+use bevy::ecs::system::EntityCommands;
+use bevy::math::bounding::BoundingCircle;
+use bevy::math::bounding::IntersectsVolume;
+use bevy::prelude::*;
+use bitflags::bitflags;
+use rand::Rng;
+use rand_pcg::Pcg32;
+use std::f32::consts::PI;
+
+pub const SWIFT_SPEED: f32 = 1.5;
+pub const VENGEFUL_SHARDS: usize = 8;
+pub const VENGEFUL_SPEED: f32 = 180.0;
+pub const VENGEFUL_LIFETIME: f32 = 2.5;
+
+pub struct AffixPlugin;
+impl Plugin for AffixPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AffixRolls::default())
+            .add_systems(
+                Update,
+                (
+                    swift,
+                    phasing,
+                    armored.before(finish_him),
+                    vengeful.after(armored).before(finish_him),
+                    update_shards,
+                    tint_elites,
+                )
+                    .run_if(in_state(PlayPhase::Fighting)),
+            )
+            .add_systems(OnExit(GameState::GameOver), destroy_shards);
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct Affixes: u32 {
+        const SWIFT = 1 << 0;
+        const ARMORED = 1 << 1;
+        const VENGEFUL = 1 << 2;
+        const PHASING = 1 << 3;
+        const CURSED = 1 << 4;
+    }
+}
+
+/// From which wave regular masks may roll affixes, and how likely they are to.
+#[derive(Resource)]
+pub struct AffixRolls {
+    pub start_wave: i32,
+    pub chance: f32,
+}
+
+impl Default for AffixRolls {
+    fn default() -> AffixRolls {
+        AffixRolls {
+            start_wave: 10,
+            chance: 0.2,
+        }
+    }
+}
+
+pub fn roll_affixes(rand: &mut Pcg32, wave: i32, rolls: &AffixRolls) -> Affixes {
+    if wave < rolls.start_wave || rand.gen::<f32>() >= rolls.chance {
+        return Affixes::empty();
+    }
+    let all: Vec<Affixes> = Affixes::all().iter().collect();
+    let mut affixes = all[rand.gen_range(0..all.len())];
+    // Every ten waves past the start, a second affix becomes possible.
+    if rand.gen::<f32>() < (wave - rolls.start_wave) as f32 / 10.0 {
+        affixes |= all[rand.gen_range(0..all.len())];
+    }
+    affixes
+}
+
+pub fn insert_affixes(enemy: &mut EntityCommands, affixes: Affixes) {
+    if affixes.is_empty() {
+        return;
+    }
+    enemy.insert(Elite);
+    if affixes.contains(Affixes::SWIFT) {
+        enemy.insert(Swift);
+    }
+    if affixes.contains(Affixes::ARMORED) {
+        enemy.insert(Armored);
+    }
+    if affixes.contains(Affixes::VENGEFUL) {
+        enemy.insert(Vengeful);
+    }
+    if affixes.contains(Affixes::PHASING) {
+        enemy.insert(Phasing);
+    }
+    if affixes.contains(Affixes::CURSED) {
+        enemy.insert(Cursed);
+    }
+}
+
+#[derive(Component)]
+pub struct Elite;
+
+#[derive(Component)]
+pub struct Swift;
+
+#[derive(Component)]
+pub struct Armored;
+
+#[derive(Component)]
+pub struct Vengeful;
+
+#[derive(Component)]
+pub struct Phasing;
+
+/// Slashes that cut it get no cooldown refund, see `hit_by_slash`.
+#[derive(Component)]
+pub struct Cursed;
+
+fn swift(mut enemies: Query<&mut Physical, Added<Swift>>) {
+    for mut physical in enemies.iter_mut() {
+        physical.top_speed *= SWIFT_SPEED;
+        physical.acceleration *= SWIFT_SPEED;
+    }
+}
+
+/// Slips through the crowd instead of being pushed apart from it.
+fn phasing(mut enemies: Query<&mut Physical, Added<Phasing>>) {
+    for mut physical in enemies.iter_mut() {
+        physical.separates = false;
+    }
+}
+
+/// Soaks up the first finisher: the mask loses its armor instead of dying.
+pub fn armored(
+    mut commands: Commands,
+    mut finish_events: EventReader<Finish>,
+    mut sound: EventWriter<Sound>,
+    enemies: Query<(Entity, &Transform), (With<Armored>, With<EnemyHit>)>,
+) {
+    if finish_events.is_empty() {
+        return;
+    }
+    finish_events.clear();
+    for (entity, transform) in enemies.iter() {
+        sound.send(Sound {
+            id: SoundId::Hit,
+            position: transform.translation,
+            speed: 0.6,
+        });
+        commands.entity(entity).remove::<(Armored, EnemyHit)>();
+    }
+}
+
+pub fn vengeful(
+    mut commands: Commands,
+    mut finish_events: EventReader<Finish>,
+    enemies: Query<&Transform, (With<Vengeful>, With<EnemyHit>)>,
+) {
+    if finish_events.is_empty() {
+        return;
+    }
+    finish_events.clear();
+    for transform in enemies.iter() {
+        for n in 0..VENGEFUL_SHARDS {
+            let direction = Vec2::from_angle(2.0 * PI * n as f32 / VENGEFUL_SHARDS as f32);
+            commands.spawn((
+                Shard {
+                    velocity: direction * VENGEFUL_SPEED,
+                    collider: BoundingCircle::new(transform.translation.truncate(), 5.0),
+                    lifetime: Timer::from_seconds(VENGEFUL_LIFETIME, TimerMode::Once),
+                },
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::srgb(4.0, 1.0, 0.0),
+                        custom_size: Some(Vec2::splat(8.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(
+                        transform.translation.truncate().extend(3.0),
+                    ),
+                    ..default()
+                },
+            ));
+        }
+    }
+}
+
+#[derive(Component)]
+struct Shard {
+    velocity: Vec2,
+    collider: BoundingCircle,
+    lifetime: Timer,
+}
+
+fn update_shards(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut hit: EventWriter<PlayerHit>,
+    mut shards: Query<(Entity, &mut Shard, &mut Transform)>,
+    mut player: Query<(&mut Physical, &PlayerCooldowns), With<Player>>,
+    walls: Query<&Wall>,
+) {
+    if player.is_empty() {
+        return;
+    }
+    let (mut player_physical, cooldowns) = player.single_mut();
+    for (entity, mut shard, mut transform) in shards.iter_mut() {
+        shard.lifetime.tick(time.delta());
+        let step = shard.velocity * time.delta_seconds();
+        transform.translation += step.extend(0.0);
+        shard.collider.center = transform.translation.truncate();
+        if walls.iter().any(|wall| {
+            wall.kind != WallKind::Border && wall.shape.contact(&shard.collider).is_some()
+        }) {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        if shard.collider.intersects(&player_physical.collider) {
+            if cooldowns.invulnerable() {
+                continue;
+            }
+            if player_physical.hit_cooldown.finished() {
+                hit.send(PlayerHit);
+                player_physical.hit_cooldown.reset();
+            }
+            commands.entity(entity).despawn_recursive();
+        } else if shard.lifetime.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn destroy_shards(mut commands: Commands, shards: Query<Entity, With<Shard>>) {
+    for shard in shards.iter() {
+        commands.entity(shard).despawn_recursive();
+    }
+}
+
+/// Colours an elite's sprite once when it spawns, and again when it loses its armor, so
+/// anything else touching the sprite's colour isn't overwritten every frame.
+fn tint_elites(
+    spawned: Query<Entity, Added<Elite>>,
+    mut unarmored: RemovedComponents<Armored>,
+    elites: Query<
+        (
+            &Children,
+            Has<Swift>,
+            Has<Armored>,
+            Has<Vengeful>,
+            Has<Phasing>,
+            Has<Cursed>,
+        ),
+        With<Elite>,
+    >,
+    mut sprites: Query<&mut Sprite, With<EnemySprite>>,
+) {
+    let changed = spawned
+        .iter()
+        .chain(unarmored.read())
+        .collect::<Vec<Entity>>();
+    for (children, swift, armored, vengeful, phasing, cursed) in elites.iter_many(changed) {
+        let tints = [
+            (swift, Vec3::new(4.0, 4.0, 0.5)),
+            (armored, Vec3::new(1.5, 1.5, 4.0)),
+            (vengeful, Vec3::new(4.0, 1.0, 0.0)),
+            (cursed, Vec3::new(2.5, 0.5, 4.0)),
+        ];
+        let active = tints.iter().filter(|(on, _)| *on).count();
+        let tint = if active > 0 {
+            tints
+                .iter()
+                .filter(|(on, _)| *on)
+                .fold(Vec3::ZERO, |sum, (_, tint)| sum + *tint)
+                / active as f32
+        } else {
+            Vec3::splat(4.0)
+        };
+        let alpha = if phasing { 0.45 } else { 1.0 };
+        for child in children.iter() {
+            if let Ok(mut sprite) = sprites.get_mut(*child) {
+                sprite.color = Color::srgba(tint.x, tint.y, tint.z, alpha);
+            }
+        }
+    }
+}
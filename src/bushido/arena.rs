@@ -0,0 +1,265 @@
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use crate::bushido::assets::GameAssets;
+use crate::bushido::collision::Shape;
+use crate::bushido::collision::Wall;
+use crate::bushido::collision::WallKind;
+use crate::bushido::enemy::SlashCapsule;
+use crate::bushido::player::Slash;
+use crate::bushido::settings::Settings;
+use crate::bushido::sound::SoundId;
+use crate::bushido::GameState;
+use crate::bushido::PlayPhase;
+use crate::bushido::Sound;
+use bevy::asset::io::Reader;
+use bevy::asset::AssetLoader;
+use bevy::asset::AsyncReadExt;
+use bevy::asset::LoadContext;
+use bevy::math::bounding::Aabb2d;
+use bevy::prelude::*;
+use bevy::sprite::MaterialMesh2dBundle;
+use std::io::Error;
+use std::io::ErrorKind;
+
+/// The arenas to pick from in the settings, by the locale key for their name and layout file.
+/// Picking none leaves the field open.
+pub const ARENAS: [(&str, &str); 2] = [
+    ("arena-dojo", "embedded://layouts/dojo.layout"),
+    ("arena-garden", "embedded://layouts/garden.layout"),
+];
+
+pub struct ArenaPlugin;
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<ArenaLayout>()
+            .register_asset_loader(ArenaLayoutLoader)
+            .add_systems(OnEnter(GameState::Play), spawn_arena)
+            .add_systems(Update, cut_lanterns.run_if(in_state(PlayPhase::Fighting)))
+            .add_systems(OnExit(GameState::GameOver), destroy_arena);
+    }
+}
+
+/// Static obstacles placed inside the play field.
+#[derive(Asset, TypePath)]
+pub struct ArenaLayout {
+    pub obstacles: Vec<(WallKind, Shape)>,
+}
+
+#[derive(Default)]
+struct ArenaLayoutLoader;
+
+impl AssetLoader for ArenaLayoutLoader {
+    type Asset = ArenaLayout;
+    type Settings = ();
+    type Error = Error;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<ArenaLayout, Error> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).await?;
+        parse_layout(&text)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["layout"]
+    }
+}
+
+/// One obstacle per line: its kind followed by numbers, `#` starts a comment.
+fn parse_layout(text: &str) -> Result<ArenaLayout, Error> {
+    let mut obstacles = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let kind = words.next().unwrap_or("");
+        let values = words
+            .map(|word| word.parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>()
+            .map_err(|error| invalid(number, &error.to_string()))?;
+        let obstacle = match (kind, values.as_slice()) {
+            ("pillar", [x, y, radius]) => (
+                WallKind::Obstacle,
+                Shape::Circle {
+                    center: Vec2::new(*x, *y),
+                    radius: *radius,
+                },
+            ),
+            ("screen", [min_x, min_y, max_x, max_y]) => (
+                WallKind::Obstacle,
+                Shape::Aabb(Aabb2d {
+                    min: Vec2::new(*min_x, *min_y),
+                    max: Vec2::new(*max_x, *max_y),
+                }),
+            ),
+            ("fence", [start_x, start_y, end_x, end_y]) => (
+                WallKind::Obstacle,
+                Shape::Segment {
+                    start: Vec2::new(*start_x, *start_y),
+                    end: Vec2::new(*end_x, *end_y),
+                },
+            ),
+            ("lantern", [x, y, radius]) => (
+                WallKind::Lantern,
+                Shape::Circle {
+                    center: Vec2::new(*x, *y),
+                    radius: *radius,
+                },
+            ),
+            _ => return Err(invalid(number, line)),
+        };
+        obstacles.push(obstacle);
+    }
+    Ok(ArenaLayout { obstacles })
+}
+
+fn invalid(number: usize, message: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("layout line {}: {}", number + 1, message),
+    )
+}
+
+#[derive(Component)]
+struct ArenaObstacle;
+
+fn spawn_arena(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    game_assets: Res<GameAssets>,
+    layouts: Res<Assets<ArenaLayout>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if settings.arena == 0 {
+        return;
+    }
+    let Some(layout) = layouts.get(&game_assets.arenas[settings.arena - 1]) else {
+        warn!("Arena layout {} isn't loaded", ARENAS[settings.arena - 1].0);
+        return;
+    };
+    for (kind, shape) in layout.obstacles.iter() {
+        let color = match kind {
+            WallKind::Lantern => Color::srgb(4.0, 1.6, 0.3),
+            _ => Color::srgb(0.35, 0.06, 0.06),
+        };
+        let mesh;
+        let transform;
+        match *shape {
+            Shape::Circle { center, radius } => {
+                mesh = meshes.add(Circle::new(radius).mesh());
+                transform = Transform::from_translation(center.extend(-10.0));
+            }
+            Shape::Aabb(aabb) => {
+                let size = aabb.max - aabb.min;
+                mesh = meshes.add(Rectangle::new(size.x, size.y).mesh());
+                transform =
+                    Transform::from_translation(((aabb.min + aabb.max) / 2.0).extend(-10.0));
+            }
+            Shape::Segment { start, end } => {
+                let line = end - start;
+                mesh = meshes.add(Rectangle::new(line.length(), 8.0).mesh());
+                transform = Transform::from_translation(((start + end) / 2.0).extend(-10.0))
+                    .with_rotation(Quat::from_rotation_z(line.to_angle()));
+            }
+            Shape::HalfPlane { .. } => continue,
+        }
+        commands.spawn((
+            ArenaObstacle,
+            Wall {
+                shape: *shape,
+                kind: *kind,
+            },
+            MaterialMesh2dBundle {
+                mesh: mesh.into(),
+                material: materials.add(color),
+                transform,
+                ..default()
+            },
+        ));
+    }
+    info!("Spawned arena {}", ARENAS[settings.arena - 1].0);
+}
+
+fn cut_lanterns(
+    mut commands: Commands,
+    mut slash_events: EventReader<Slash>,
+    mut sound: EventWriter<Sound>,
+    walls: Query<(Entity, &Wall)>,
+) {
+    for line in slash_events.read() {
+        let capsule = SlashCapsule::new(line.start, line.direction, line.length, line.width);
+        for (entity, wall) in walls.iter() {
+            if wall.kind == WallKind::Lantern && capsule.intersects(&wall.shape) {
+                sound.send(Sound {
+                    id: SoundId::Kill,
+                    position: line.start.extend(0.0),
+                    speed: 1.4,
+                });
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn destroy_arena(mut commands: Commands, obstacles: Query<Entity, With<ArenaObstacle>>) {
+    for obstacle in obstacles.iter() {
+        commands.entity(obstacle).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_kind_and_skips_comments() {
+        let layout = parse_layout(
+            "# dojo\n\npillar 0 0 10\nscreen -5 -5 5 5 # folding\nfence 0 0 10 0\nlantern 1 2 3\n",
+        )
+        .unwrap();
+        assert_eq!(layout.obstacles.len(), 4);
+        assert!(matches!(
+            layout.obstacles[0],
+            (WallKind::Obstacle, Shape::Circle { radius, .. }) if radius == 10.0
+        ));
+        assert!(matches!(
+            layout.obstacles[1],
+            (WallKind::Obstacle, Shape::Aabb(aabb)) if aabb.max == Vec2::splat(5.0)
+        ));
+        assert!(matches!(
+            layout.obstacles[2],
+            (WallKind::Obstacle, Shape::Segment { end, .. }) if end == Vec2::new(10.0, 0.0)
+        ));
+        assert!(matches!(
+            layout.obstacles[3],
+            (WallKind::Lantern, Shape::Circle { center, .. }) if center == Vec2::new(1.0, 2.0)
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        for line in [
+            "pillar 0 0",
+            "pillar 0 0 10 4",
+            "pillar 0 zero 10",
+            "screen 0 0 5",
+            "fence 0 0 10 0 5",
+            "lantern",
+            "tree 0 0 10",
+            "0 0 10",
+        ] {
+            let error = parse_layout(&format!("pillar 0 0 1\n{}", line)).err();
+            assert!(
+                error.is_some_and(|error| error.to_string().starts_with("layout line 2:")),
+                "{:?} parsed",
+                line
+            );
+        }
+    }
+}
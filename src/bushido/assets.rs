@@ -0,0 +1,186 @@
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use crate::bushido::accessibility::PROMPT_ANSWERED;
+use crate::bushido::arena::ArenaLayout;
+use crate::bushido::arena::ARENAS;
+use crate::bushido::locale::Language;
+use crate::bushido::locale::Locale;
+use crate::bushido::music::MusicTrack;
+use crate::bushido::save::SaveFile;
+use crate::bushido::sound::SoundId;
+use crate::bushido::GameState;
+use bevy::asset::LoadState;
+use bevy::asset::RecursiveDependencyLoadState;
+use bevy::asset::UntypedAssetId;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+/// Fonts for whatever saruji can't draw, each shipped with its license next to it. A language
+/// whose script none of them covers needs one added here before its strings will render.
+const FALLBACK_FONTS: [&str; 1] = ["DejaVuSans.ttf"];
+
+pub struct GameAssetsPlugin;
+impl Plugin for GameAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, load_game_assets)
+            .add_systems(Update, wait_for_assets.run_if(in_state(GameState::Loading)));
+    }
+}
+
+/// Every handle the game uses, loaded once up front. Startup systems can rely on it being there,
+/// but the files themselves are only ready once `GameState::Loading` is over.
+#[derive(Resource)]
+pub struct GameAssets {
+    pub font: Handle<Font>,
+    /// Tried in order for any text saruji has no glyphs for, see `Localizer::font`.
+    pub fallback_fonts: Vec<Handle<Font>>,
+    pub player_top: Handle<Image>,
+    pub player_bottom: Handle<Image>,
+    pub dummy: Handle<Image>,
+    pub gray_mask: Handle<Image>,
+    pub blue_mask: Handle<Image>,
+    pub red_mask: Handle<Image>,
+    pub black_mask: Handle<Image>,
+    pub dark_presence: Handle<Image>,
+    pub dark_presence_attack: Handle<Image>,
+    /// Five 20 pixel frames, for the player and the mirror.
+    pub player_layout: Handle<TextureAtlasLayout>,
+    /// Four 16 pixel frames, for the masks.
+    pub mask_layout: Handle<TextureAtlasLayout>,
+    /// The training dummy's single 20 pixel frame.
+    pub dummy_layout: Handle<TextureAtlasLayout>,
+    /// In the same order as `ARENAS`.
+    pub arenas: Vec<Handle<ArenaLayout>>,
+    /// The adaptive score, which brings its stems in with it.
+    pub music: Handle<MusicTrack>,
+    /// In the same order as `Language::ALL`.
+    pub locales: Vec<Handle<Locale>>,
+    sounds: HashMap<SoundId, Handle<AudioSource>>,
+}
+
+impl GameAssets {
+    pub fn sound(&self, id: SoundId) -> Handle<AudioSource> {
+        self.sounds[&id].clone()
+    }
+
+    /// The handles that come from files, which are the ones worth waiting on.
+    fn loading(&self) -> Vec<UntypedAssetId> {
+        let mut ids = vec![
+            self.font.id().untyped(),
+            self.player_top.id().untyped(),
+            self.player_bottom.id().untyped(),
+            self.dummy.id().untyped(),
+            self.gray_mask.id().untyped(),
+            self.blue_mask.id().untyped(),
+            self.red_mask.id().untyped(),
+            self.black_mask.id().untyped(),
+            self.dark_presence.id().untyped(),
+            self.dark_presence_attack.id().untyped(),
+        ];
+        ids.extend(
+            self.fallback_fonts
+                .iter()
+                .map(|handle| handle.id().untyped()),
+        );
+        ids.push(self.music.id().untyped());
+        ids.extend(self.locales.iter().map(|handle| handle.id().untyped()));
+        ids.extend(self.arenas.iter().map(|handle| handle.id().untyped()));
+        ids.extend(self.sounds.values().map(|handle| handle.id().untyped()));
+        ids
+    }
+}
+
+fn load_game_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    commands.insert_resource(GameAssets {
+        font: asset_server.load("embedded://saruji.ttf"),
+        fallback_fonts: FALLBACK_FONTS
+            .iter()
+            .map(|file| asset_server.load(format!("embedded://{}", file)))
+            .collect(),
+        player_top: asset_server.load("embedded://PlayerTopQuartered.png"),
+        player_bottom: asset_server.load("embedded://PlayerBottom.png"),
+        dummy: asset_server.load("embedded://Dummy.png"),
+        gray_mask: asset_server.load("embedded://GrayMask.png"),
+        blue_mask: asset_server.load("embedded://BlueMask.png"),
+        red_mask: asset_server.load("embedded://RedMask.png"),
+        black_mask: asset_server.load("embedded://BlackMask.png"),
+        dark_presence: asset_server.load("embedded://DarkPresence.png"),
+        dark_presence_attack: asset_server.load("embedded://DarkPresenceAttack.png"),
+        player_layout: layouts.add(TextureAtlasLayout::from_grid(
+            UVec2::splat(20),
+            5,
+            1,
+            None,
+            None,
+        )),
+        mask_layout: layouts.add(TextureAtlasLayout::from_grid(
+            UVec2::splat(16),
+            4,
+            1,
+            None,
+            None,
+        )),
+        dummy_layout: layouts.add(TextureAtlasLayout::from_grid(
+            UVec2::splat(20),
+            1,
+            1,
+            None,
+            None,
+        )),
+        arenas: ARENAS
+            .iter()
+            .map(|(_, path)| asset_server.load(*path))
+            .collect(),
+        music: asset_server.load("embedded://music/score.music"),
+        locales: Language::ALL
+            .iter()
+            .map(|language| {
+                asset_server.load(format!("embedded://locale/{}.lang", language.code()))
+            })
+            .collect(),
+        sounds: SoundId::ALL
+            .iter()
+            .map(|id| {
+                let handle = asset_server.load(format!("embedded://{}", id.def().file));
+                (*id, handle)
+            })
+            .collect(),
+    });
+}
+
+/// Holds off the launch prompt, or the title once the prompt has been answered on an earlier
+/// launch, until everything is in. A file that fails to load is logged and skipped rather than
+/// leaving the game stuck here.
+fn wait_for_assets(
+    assets: Res<GameAssets>,
+    save: Res<SaveFile>,
+    asset_server: Res<AssetServer>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let mut waiting = false;
+    for id in assets.loading() {
+        if let Some(LoadState::Failed(error)) = asset_server.get_load_state(id) {
+            error!("Failed to load an asset: {}", error);
+        } else if let Some(RecursiveDependencyLoadState::Failed) =
+            asset_server.get_recursive_dependency_load_state(id)
+        {
+            error!(
+                "Failed to load part of an asset: {:?}",
+                asset_server.get_path(id)
+            );
+        } else if !asset_server.is_loaded_with_dependencies(id) {
+            waiting = true;
+        }
+    }
+    if !waiting {
+        info!("Assets loaded");
+        if save.get(PROMPT_ANSWERED) == Some(true) {
+            next_state.set(GameState::Menu);
+        } else {
+            next_state.set(GameState::Prompt);
+        }
+    }
+}
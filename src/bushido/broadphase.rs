@@ -0,0 +1,205 @@
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use bevy::math::bounding::BoundingCircle;
+use bevy::math::bounding::IntersectsVolume;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+/// Matches the range enemies start steering apart at, so a pair query only looks one cell out.
+pub const CELL_SIZE: f32 = 100.0;
+
+/// A uniform grid of colliders, bucketed by the cell their centre falls in. Rebuilt from scratch
+/// every tick, so nothing needs removing.
+#[derive(Resource)]
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Entity, BoundingCircle)>>,
+    /// The biggest radius inserted since the last clear, so queries can reach far enough for it.
+    largest: f32,
+}
+
+impl Default for SpatialHash {
+    fn default() -> SpatialHash {
+        SpatialHash::new(CELL_SIZE)
+    }
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> SpatialHash {
+        SpatialHash {
+            cell_size,
+            cells: HashMap::default(),
+            largest: 0.0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        // Emptying the buckets in place keeps their allocations for the next rebuild.
+        self.cells.retain(|_, bucket| !bucket.is_empty());
+        for bucket in self.cells.values_mut() {
+            bucket.clear();
+        }
+        self.largest = 0.0;
+    }
+
+    pub fn insert(&mut self, entity: Entity, collider: BoundingCircle) {
+        self.largest = f32::max(self.largest, collider.radius());
+        let cell = self.cell(collider.center);
+        self.cells.entry(cell).or_default().push((entity, collider));
+    }
+
+    fn cell(&self, point: Vec2) -> IVec2 {
+        (point / self.cell_size).floor().as_ivec2()
+    }
+
+    /// Every collider that might come within `reach` of the box from `min` to `max`. Callers still
+    /// need to do the exact test.
+    pub fn query_aabb(
+        &self,
+        min: Vec2,
+        max: Vec2,
+        reach: f32,
+    ) -> impl Iterator<Item = (Entity, BoundingCircle)> + '_ {
+        let margin = Vec2::splat(reach + self.largest);
+        let low = self.cell(min - margin);
+        let high = self.cell(max + margin);
+        (low.x..=high.x)
+            .flat_map(move |x| (low.y..=high.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+    }
+
+    pub fn query_circle(
+        &self,
+        center: Vec2,
+        reach: f32,
+    ) -> impl Iterator<Item = (Entity, BoundingCircle)> + '_ {
+        self.query_aabb(center, center, reach)
+    }
+
+    pub fn query_segment(
+        &self,
+        start: Vec2,
+        end: Vec2,
+        reach: f32,
+    ) -> impl Iterator<Item = (Entity, BoundingCircle)> + '_ {
+        self.query_aabb(start.min(end), start.max(end), reach)
+    }
+
+    /// Each pair of colliders that overlap or have centres less than `within` apart, once.
+    pub fn pairs(&self, within: f32) -> Vec<(Entity, Entity)> {
+        let mut pairs = Vec::new();
+        for (entity, collider) in self.cells.values().flatten() {
+            for (other, other_collider) in self.query_circle(collider.center, within) {
+                if other <= *entity {
+                    continue;
+                }
+                if collider.center.distance(other_collider.center) < within
+                    || collider.intersects(&other_collider)
+                {
+                    pairs.push((*entity, other));
+                }
+            }
+        }
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg32;
+
+    /// Mostly mask sized colliders, with every fifth one wider than a cell.
+    fn crowd(seed: u64) -> Vec<(Entity, BoundingCircle)> {
+        let mut rng = Pcg32::seed_from_u64(seed);
+        (0..200)
+            .map(|index| {
+                let center = Vec2::new(rng.gen_range(-600.0..600.0), rng.gen_range(-600.0..600.0));
+                let radius = if index % 5 == 0 {
+                    rng.gen_range(CELL_SIZE..CELL_SIZE * 2.5)
+                } else {
+                    rng.gen_range(5.0..20.0)
+                };
+                (Entity::from_raw(index), BoundingCircle::new(center, radius))
+            })
+            .collect()
+    }
+
+    fn hashed(crowd: &[(Entity, BoundingCircle)]) -> SpatialHash {
+        let mut hash = SpatialHash::default();
+        for (entity, collider) in crowd {
+            hash.insert(*entity, *collider);
+        }
+        hash
+    }
+
+    fn closest_on_segment(start: Vec2, end: Vec2, point: Vec2) -> Vec2 {
+        let line = end - start;
+        let t = ((point - start).dot(line) / line.length_squared()).clamp(0.0, 1.0);
+        start + line * t
+    }
+
+    #[test]
+    fn pairs_match_brute_force() {
+        for seed in 0..4 {
+            let crowd = crowd(seed);
+            let mut expected = Vec::new();
+            for (index, (entity, collider)) in crowd.iter().enumerate() {
+                for (other, other_collider) in &crowd[index + 1..] {
+                    if collider.center.distance(other_collider.center) < CELL_SIZE
+                        || collider.intersects(other_collider)
+                    {
+                        expected.push((*entity, *other));
+                    }
+                }
+            }
+            let mut pairs = hashed(&crowd).pairs(CELL_SIZE);
+            pairs.sort();
+            expected.sort();
+            assert_eq!(pairs, expected);
+        }
+    }
+
+    #[test]
+    fn circle_queries_find_everything_in_reach() {
+        let crowd = crowd(7);
+        let hash = hashed(&crowd);
+        let mut rng = Pcg32::seed_from_u64(8);
+        for _ in 0..50 {
+            let center = Vec2::new(rng.gen_range(-700.0..700.0), rng.gen_range(-700.0..700.0));
+            let reach = rng.gen_range(0.0..150.0);
+            let found = hash.query_circle(center, reach).collect::<Vec<_>>();
+            for (entity, collider) in &crowd {
+                if collider.center.distance(center) - collider.radius() <= reach {
+                    assert!(found.iter().any(|(other, _)| other == entity));
+                }
+            }
+            let mut unique = found.iter().map(|(entity, _)| *entity).collect::<Vec<_>>();
+            unique.sort();
+            unique.dedup();
+            assert_eq!(unique.len(), found.len());
+        }
+    }
+
+    #[test]
+    fn segment_queries_find_everything_in_reach() {
+        let crowd = crowd(9);
+        let hash = hashed(&crowd);
+        let mut rng = Pcg32::seed_from_u64(10);
+        for _ in 0..50 {
+            let start = Vec2::new(rng.gen_range(-700.0..700.0), rng.gen_range(-700.0..700.0));
+            let end = start + Vec2::from_angle(rng.gen_range(0.0..6.3)) * 300.0;
+            let reach = rng.gen_range(0.0..30.0);
+            let found = hash.query_segment(start, end, reach).collect::<Vec<_>>();
+            for (entity, collider) in &crowd {
+                let closest = closest_on_segment(start, end, collider.center);
+                if collider.center.distance(closest) - collider.radius() <= reach {
+                    assert!(found.iter().any(|(other, _)| other == entity));
+                }
+            }
+        }
+    }
+}
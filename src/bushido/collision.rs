@@ -0,0 +1,650 @@
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use crate::bushido::Physical;
+use crate::GameGlobal;
+use bevy::math::bounding::Aabb2d;
+use bevy::math::bounding::BoundingCircle;
+use bevy::math::bounding::BoundingVolume;
+use bevy::math::bounding::RayCast2d;
+use bevy::prelude::*;
+
+/// The static shapes that bodies, slashes and projectiles run into.
+#[derive(Clone, Copy, Debug)]
+pub enum Shape {
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+    Aabb(Aabb2d),
+    Segment {
+        start: Vec2,
+        end: Vec2,
+    },
+    /// Everything behind the line through `point`, with `normal` pointing out of it.
+    HalfPlane {
+        point: Vec2,
+        normal: Vec2,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WallKind {
+    /// The inner window border. Slashes only stop at it while the window isn't expanded.
+    Border,
+    Obstacle,
+    /// Solid to bodies, but a slash cuts straight through and breaks it.
+    Lantern,
+}
+
+impl WallKind {
+    /// Inside the window the border holds; once it is expanded, slashing across it is how the
+    /// player gets out. Obstacles always hold, and lanterns get cut through.
+    pub fn stops_slash(&self, expanded: bool) -> bool {
+        match self {
+            WallKind::Border => !expanded,
+            WallKind::Obstacle => true,
+            WallKind::Lantern => false,
+        }
+    }
+
+    /// Whether a slash touching this wall gets its cooldown back. The border only pays out for
+    /// slashing across it once the window is expanded; before that, a slash it actually stops is
+    /// already `Slash::blocked`.
+    pub fn refunds_slash(&self, expanded: bool) -> bool {
+        match self {
+            WallKind::Border => expanded,
+            WallKind::Obstacle => true,
+            WallKind::Lantern => false,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Wall {
+    pub shape: Shape,
+    pub kind: WallKind,
+}
+
+/// Which way to push a circle out of a shape, and how far.
+#[derive(Clone, Copy, Debug)]
+pub struct Contact {
+    pub normal: Vec2,
+    pub depth: f32,
+}
+
+fn closest_on_segment(start: Vec2, end: Vec2, point: Vec2) -> Vec2 {
+    let line = end - start;
+    if line.length_squared() <= 0.0 {
+        return start;
+    }
+    let t = ((point - start).dot(line) / line.length_squared()).clamp(0.0, 1.0);
+    start + line * t
+}
+
+/// Whether two segments cross each other. Parallel ones never do.
+pub fn segments_cross(a_start: Vec2, a_end: Vec2, b_start: Vec2, b_end: Vec2) -> bool {
+    let a = a_end - a_start;
+    let b = b_end - b_start;
+    let denominator = a.perp_dot(b);
+    if f32::abs(denominator) < f32::EPSILON {
+        return false;
+    }
+    let offset = b_start - a_start;
+    let t = offset.perp_dot(b) / denominator;
+    let u = offset.perp_dot(a) / denominator;
+    (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)
+}
+
+/// Distance along a ray to where it crosses the segment from `a` to `b`.
+fn ray_segment(ray: &RayCast2d, a: Vec2, b: Vec2) -> Option<f32> {
+    let direction = *ray.ray.direction;
+    let edge = b - a;
+    let denominator = direction.perp_dot(edge);
+    if f32::abs(denominator) < f32::EPSILON {
+        return None;
+    }
+    let offset = a - ray.ray.origin;
+    let t = offset.perp_dot(edge) / denominator;
+    let u = offset.perp_dot(direction) / denominator;
+    if (0.0..=ray.max).contains(&t) && (0.0..=1.0).contains(&u) {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+fn ray_circle(ray: &RayCast2d, center: Vec2, radius: f32) -> Option<f32> {
+    ray.circle_intersection_at(&BoundingCircle::new(center, radius))
+}
+
+fn aabb_corners(aabb: &Aabb2d) -> [Vec2; 4] {
+    [
+        aabb.min,
+        Vec2::new(aabb.min.x, aabb.max.y),
+        aabb.max,
+        Vec2::new(aabb.max.x, aabb.min.y),
+    ]
+}
+
+impl Shape {
+    /// How a circle overlaps this shape, if it does at all.
+    pub fn contact(&self, circle: &BoundingCircle) -> Option<Contact> {
+        let center = circle.center;
+        let radius = circle.radius();
+        match *self {
+            Shape::Circle {
+                center: shape_center,
+                radius: shape_radius,
+            } => {
+                let apart = center - shape_center;
+                let depth = radius + shape_radius - apart.length();
+                (depth > 0.0).then(|| Contact {
+                    normal: apart.try_normalize().unwrap_or(Vec2::Y),
+                    depth,
+                })
+            }
+            Shape::Segment { start, end } => {
+                let apart = center - closest_on_segment(start, end, center);
+                let depth = radius - apart.length();
+                (depth > 0.0).then(|| Contact {
+                    normal: apart
+                        .try_normalize()
+                        .unwrap_or((end - start).perp().normalize_or_zero()),
+                    depth,
+                })
+            }
+            Shape::HalfPlane { point, normal } => {
+                let depth = radius - (center - point).dot(normal);
+                (depth > 0.0).then_some(Contact { normal, depth })
+            }
+            Shape::Aabb(aabb) => {
+                let closest = aabb.closest_point(center);
+                if closest != center {
+                    let apart = center - closest;
+                    let depth = radius - apart.length();
+                    return (depth > 0.0).then(|| Contact {
+                        normal: apart.normalize(),
+                        depth,
+                    });
+                }
+                // The centre is inside the box, so leave through the nearest side.
+                let sides = [
+                    (Vec2::NEG_X, center.x - aabb.min.x),
+                    (Vec2::X, aabb.max.x - center.x),
+                    (Vec2::NEG_Y, center.y - aabb.min.y),
+                    (Vec2::Y, aabb.max.y - center.y),
+                ];
+                let (normal, inside) = sides
+                    .into_iter()
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .unwrap();
+                Some(Contact {
+                    normal,
+                    depth: radius + inside,
+                })
+            }
+        }
+    }
+
+    /// The shortest distance between this shape and the segment from `start` to `end`.
+    pub fn distance_to_segment(&self, start: Vec2, end: Vec2) -> f32 {
+        match *self {
+            Shape::Circle { center, radius } => f32::max(
+                center.distance(closest_on_segment(start, end, center)) - radius,
+                0.0,
+            ),
+            Shape::Segment {
+                start: other_start,
+                end: other_end,
+            } => {
+                if segments_cross(start, end, other_start, other_end) {
+                    return 0.0;
+                }
+                [
+                    other_start.distance(closest_on_segment(start, end, other_start)),
+                    other_end.distance(closest_on_segment(start, end, other_end)),
+                    start.distance(closest_on_segment(other_start, other_end, start)),
+                    end.distance(closest_on_segment(other_start, other_end, end)),
+                ]
+                .into_iter()
+                .fold(f32::MAX, f32::min)
+            }
+            Shape::HalfPlane { point, normal } => f32::max(
+                f32::min((start - point).dot(normal), (end - point).dot(normal)),
+                0.0,
+            ),
+            Shape::Aabb(aabb) => {
+                if let Ok(direction) = Dir2::new(end - start) {
+                    let ray = RayCast2d::new(start, direction, start.distance(end));
+                    if ray.aabb_intersection_at(&aabb).is_some() {
+                        return 0.0;
+                    }
+                }
+                // Apart from crossing, the closest approach is always at a corner or at an end of
+                // the segment.
+                aabb_corners(&aabb)
+                    .into_iter()
+                    .map(|corner| corner.distance(closest_on_segment(start, end, corner)))
+                    .chain([
+                        aabb.closest_point(start).distance(start),
+                        aabb.closest_point(end).distance(end),
+                    ])
+                    .fold(f32::MAX, f32::min)
+            }
+        }
+    }
+
+    /// How far along `ray` a circle of `radius` travels before it touches this shape.
+    fn cast(&self, ray: &RayCast2d, radius: f32) -> Option<f32> {
+        let hits: Vec<Option<f32>> = match *self {
+            Shape::Circle {
+                center,
+                radius: shape_radius,
+            } => vec![ray_circle(ray, center, shape_radius + radius)],
+            Shape::Segment { start, end } => {
+                let offset = (end - start).perp().normalize_or_zero() * radius;
+                vec![
+                    ray_circle(ray, start, radius),
+                    ray_circle(ray, end, radius),
+                    ray_segment(ray, start + offset, end + offset),
+                    ray_segment(ray, start - offset, end - offset),
+                ]
+            }
+            Shape::HalfPlane { point, normal } => {
+                let closing = ray.ray.direction.dot(normal);
+                let t = (radius - (ray.ray.origin - point).dot(normal)) / closing;
+                vec![(closing < 0.0 && (0.0..=ray.max).contains(&t)).then_some(t)]
+            }
+            Shape::Aabb(aabb) => {
+                let mut hits = vec![
+                    ray.aabb_intersection_at(&Aabb2d {
+                        min: aabb.min - Vec2::X * radius,
+                        max: aabb.max + Vec2::X * radius,
+                    }),
+                    ray.aabb_intersection_at(&Aabb2d {
+                        min: aabb.min - Vec2::Y * radius,
+                        max: aabb.max + Vec2::Y * radius,
+                    }),
+                ];
+                for corner in aabb_corners(&aabb) {
+                    hits.push(ray_circle(ray, corner, radius));
+                }
+                hits
+            }
+        };
+        hits.into_iter().flatten().reduce(f32::min)
+    }
+}
+
+/// How far a circle of `radius` gets from `start` along `direction` before it runs into one of
+/// `shapes`, and the normal of the shape it stops against. Starting out already touching a shape
+/// only counts as blocked when heading further into it.
+pub fn sweep<'a>(
+    start: Vec2,
+    direction: Dir2,
+    length: f32,
+    radius: f32,
+    shapes: impl Iterator<Item = &'a Shape>,
+) -> (f32, Option<Vec2>) {
+    let ray = RayCast2d::new(start, direction, length);
+    let mut travel = length;
+    let mut normal = None;
+    for shape in shapes {
+        if let Some(contact) = shape.contact(&BoundingCircle::new(start, radius)) {
+            if contact.normal.dot(*direction) < 0.0 {
+                return (0.0, Some(contact.normal));
+            }
+            continue;
+        }
+        let Some(hit) = shape.cast(&ray, radius) else {
+            continue;
+        };
+        if hit < travel {
+            travel = hit;
+            let stop = BoundingCircle::new(start + *direction * hit, radius + 0.01);
+            normal = Some(
+                shape
+                    .contact(&stop)
+                    .map_or(-*direction, |contact| contact.normal),
+            );
+        }
+    }
+    (travel, normal)
+}
+
+/// One straight stretch of a path that may bounce off walls.
+#[derive(Clone, Copy, Debug)]
+pub struct Leg {
+    pub start: Vec2,
+    pub direction: Dir2,
+    pub length: f32,
+    /// The normal of the wall that stopped this leg, if one did.
+    pub normal: Option<Vec2>,
+}
+
+impl Leg {
+    pub fn end(&self) -> Vec2 {
+        self.start + *self.direction * self.length
+    }
+}
+
+/// `direction` mirrored off a surface facing `normal`.
+pub fn reflect(direction: Dir2, normal: Vec2) -> Option<Dir2> {
+    Dir2::new(*direction - 2.0 * direction.dot(normal) * normal).ok()
+}
+
+/// Sweeps a circle like `sweep` through `walls`, and each time the window border stops it,
+/// reflects what's left of `length` off the border, up to `bounces` times. Any other wall is a
+/// plain stop. Only the last leg can end against a wall that held.
+pub fn ricochet(
+    start: Vec2,
+    direction: Dir2,
+    length: f32,
+    radius: f32,
+    walls: &[&Wall],
+    bounces: u32,
+) -> Vec<Leg> {
+    let mut legs = Vec::new();
+    let mut start = start;
+    let mut direction = direction;
+    let mut remaining = length;
+    let shapes = || walls.iter().map(|wall| &wall.shape);
+    let borders = || {
+        walls
+            .iter()
+            .filter(|wall| wall.kind == WallKind::Border)
+            .map(|wall| &wall.shape)
+    };
+    loop {
+        let (travel, normal) = sweep(start, direction, remaining, radius, shapes());
+        let leg = Leg {
+            start,
+            direction,
+            length: travel,
+            normal,
+        };
+        legs.push(leg);
+        remaining -= travel;
+        let Some(normal) = normal else {
+            break;
+        };
+        if legs.len() as u32 > bounces || remaining <= 0.0 {
+            break;
+        }
+        // Something short of the border got there first.
+        let (border_travel, _) = sweep(start, direction, remaining + travel, radius, borders());
+        if border_travel > travel {
+            break;
+        }
+        let Some(reflected) = reflect(direction, normal) else {
+            break;
+        };
+        start = leg.end();
+        direction = reflected;
+    }
+    legs
+}
+
+/// How far from the origin bodies may go, which grows when the window is expanded.
+pub fn field_half_size(inner_world_size: Vec2, expanded: bool) -> Vec2 {
+    if expanded {
+        inner_world_size * 2.0 / 3.0
+    } else {
+        inner_world_size / 2.0
+    }
+}
+
+/// The four half-planes that keep bodies within `half_size` of the origin.
+pub fn bounds(half_size: Vec2) -> [Shape; 4] {
+    [Vec2::X, Vec2::NEG_X, Vec2::Y, Vec2::NEG_Y].map(|normal| Shape::HalfPlane {
+        point: -normal * half_size,
+        normal,
+    })
+}
+
+/// Moves a circle out of every shape it overlaps, in order, and takes away only the part of its
+/// velocity that heads into them. Returns the new position and velocity.
+pub fn resolve<'a>(
+    mut position: Vec2,
+    mut velocity: Vec2,
+    radius: f32,
+    shapes: impl Iterator<Item = &'a Shape>,
+) -> (Vec2, Vec2) {
+    for shape in shapes {
+        let Some(contact) = shape.contact(&BoundingCircle::new(position, radius)) else {
+            continue;
+        };
+        position += contact.normal * contact.depth;
+        let into = velocity.dot(contact.normal);
+        if into < 0.0 {
+            velocity -= contact.normal * into;
+        }
+    }
+    (position, velocity)
+}
+
+/// Keeps every body out of the walls and inside the play field, which grows when the window is
+/// expanded.
+pub fn collide_with_walls(
+    global: Res<GameGlobal>,
+    walls: Query<&Wall>,
+    mut things: Query<(&mut Transform, &mut Physical)>,
+) {
+    let bounds = bounds(field_half_size(global.inner_world_size, global.expanded));
+
+    for (mut transform, mut physical) in things.iter_mut() {
+        let (position, velocity) = resolve(
+            transform.translation.truncate(),
+            physical.velocity,
+            physical.collider.radius() + physical.wall_padding,
+            walls.iter().map(|wall| &wall.shape).chain(bounds.iter()),
+        );
+        transform.translation = position.extend(transform.translation.z);
+        physical.velocity = velocity;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-3;
+
+    fn square(center: Vec2, half_size: f32) -> Shape {
+        Shape::Aabb(Aabb2d::new(center, Vec2::splat(half_size)))
+    }
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < EPSILON, "{} is not {}", a, b);
+    }
+
+    #[test]
+    fn circle_contact() {
+        let shape = Shape::Circle {
+            center: Vec2::ZERO,
+            radius: 10.0,
+        };
+        let contact = shape
+            .contact(&BoundingCircle::new(Vec2::new(15.0, 0.0), 10.0))
+            .unwrap();
+        assert_near(contact.normal, Vec2::X);
+        assert!((contact.depth - 5.0).abs() < EPSILON);
+        assert!(shape
+            .contact(&BoundingCircle::new(Vec2::new(25.0, 0.0), 4.0))
+            .is_none());
+    }
+
+    #[test]
+    fn aabb_contact_outside_and_inside() {
+        let shape = square(Vec2::ZERO, 10.0);
+        let outside = shape
+            .contact(&BoundingCircle::new(Vec2::new(0.0, 13.0), 5.0))
+            .unwrap();
+        assert_near(outside.normal, Vec2::Y);
+        assert!((outside.depth - 2.0).abs() < EPSILON);
+
+        // The centre is 2 from the right side, so it leaves that way, by its radius and then some.
+        let inside = shape
+            .contact(&BoundingCircle::new(Vec2::new(8.0, 0.0), 3.0))
+            .unwrap();
+        assert_near(inside.normal, Vec2::X);
+        assert!((inside.depth - 5.0).abs() < EPSILON);
+
+        assert!(shape
+            .contact(&BoundingCircle::new(Vec2::new(20.0, 20.0), 5.0))
+            .is_none());
+    }
+
+    #[test]
+    fn segment_contact() {
+        let shape = Shape::Segment {
+            start: Vec2::new(-10.0, 0.0),
+            end: Vec2::new(10.0, 0.0),
+        };
+        let side = shape
+            .contact(&BoundingCircle::new(Vec2::new(0.0, -3.0), 5.0))
+            .unwrap();
+        assert_near(side.normal, Vec2::NEG_Y);
+        assert!((side.depth - 2.0).abs() < EPSILON);
+
+        let past_end = shape
+            .contact(&BoundingCircle::new(Vec2::new(13.0, 0.0), 5.0))
+            .unwrap();
+        assert_near(past_end.normal, Vec2::X);
+        assert!((past_end.depth - 2.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn resolve_only_stops_velocity_into_the_shape() {
+        let shapes = [square(Vec2::ZERO, 10.0)];
+        let (position, velocity) = resolve(
+            Vec2::new(13.0, 0.0),
+            Vec2::new(-3.0, 2.0),
+            5.0,
+            shapes.iter(),
+        );
+        assert_near(position, Vec2::new(15.0, 0.0));
+        assert_near(velocity, Vec2::new(0.0, 2.0));
+
+        let (_, velocity) = resolve(
+            Vec2::new(13.0, 0.0),
+            Vec2::new(3.0, 2.0),
+            5.0,
+            shapes.iter(),
+        );
+        assert_near(velocity, Vec2::new(3.0, 2.0));
+    }
+
+    #[test]
+    fn sweep_stops_at_first_shape() {
+        let shapes = [
+            square(Vec2::new(60.0, 0.0), 10.0),
+            Shape::Circle {
+                center: Vec2::new(90.0, 0.0),
+                radius: 5.0,
+            },
+        ];
+        let (travel, normal) = sweep(Vec2::ZERO, Dir2::X, 100.0, 5.0, shapes.iter());
+        assert!((travel - 45.0).abs() < EPSILON);
+        assert_near(normal.unwrap(), Vec2::NEG_X);
+
+        let (travel, normal) = sweep(Vec2::ZERO, Dir2::X, 30.0, 5.0, shapes.iter());
+        assert!((travel - 30.0).abs() < EPSILON);
+        assert!(normal.is_none());
+    }
+
+    #[test]
+    fn sweep_leaves_a_touching_shape_freely() {
+        let shapes = [Shape::Circle {
+            center: Vec2::new(-8.0, 0.0),
+            radius: 5.0,
+        }];
+        let (travel, normal) = sweep(Vec2::ZERO, Dir2::X, 50.0, 5.0, shapes.iter());
+        assert!((travel - 50.0).abs() < EPSILON);
+        assert!(normal.is_none());
+
+        let (travel, normal) = sweep(Vec2::ZERO, Dir2::NEG_X, 50.0, 5.0, shapes.iter());
+        assert_eq!(travel, 0.0);
+        assert_near(normal.unwrap(), Vec2::X);
+    }
+
+    #[test]
+    fn ricochet_reflects_the_rest_of_the_length() {
+        let wall = Wall {
+            shape: Shape::HalfPlane {
+                point: Vec2::new(30.0, 0.0),
+                normal: Vec2::NEG_X,
+            },
+            kind: WallKind::Border,
+        };
+        let direction = Dir2::new(Vec2::new(1.0, 1.0)).unwrap();
+
+        let legs = ricochet(Vec2::ZERO, direction, 100.0, 5.0, &[&wall], 1);
+        assert_eq!(legs.len(), 2);
+        assert_near(legs[0].end(), Vec2::new(25.0, 25.0));
+        assert_near(legs[0].normal.unwrap(), Vec2::NEG_X);
+        assert_near(*legs[1].direction, Vec2::new(-1.0, 1.0).normalize());
+        assert!(legs[1].normal.is_none());
+        assert!((legs[0].length + legs[1].length - 100.0).abs() < EPSILON);
+        let rest = 100.0 - 25.0 * std::f32::consts::SQRT_2;
+        assert_near(
+            legs[1].end(),
+            Vec2::new(25.0, 25.0) + Vec2::new(-1.0, 1.0).normalize() * rest,
+        );
+
+        let legs = ricochet(Vec2::ZERO, direction, 100.0, 5.0, &[&wall], 0);
+        assert_eq!(legs.len(), 1);
+        assert!(legs[0].normal.is_some());
+    }
+
+    #[test]
+    fn ricochet_stops_at_obstacles() {
+        let border = Wall {
+            shape: Shape::HalfPlane {
+                point: Vec2::new(60.0, 0.0),
+                normal: Vec2::NEG_X,
+            },
+            kind: WallKind::Border,
+        };
+        let pillar = Wall {
+            shape: Shape::Circle {
+                center: Vec2::new(30.0, 0.0),
+                radius: 10.0,
+            },
+            kind: WallKind::Obstacle,
+        };
+        let legs = ricochet(Vec2::ZERO, Dir2::X, 100.0, 5.0, &[&pillar, &border], 1);
+        assert_eq!(legs.len(), 1);
+        assert_near(legs[0].end(), Vec2::new(15.0, 0.0));
+        assert_near(legs[0].normal.unwrap(), Vec2::NEG_X);
+
+        let legs = ricochet(Vec2::ZERO, Dir2::X, 100.0, 5.0, &[&border], 1);
+        assert_eq!(legs.len(), 2);
+        assert_near(legs[0].end(), Vec2::new(55.0, 0.0));
+        assert_near(*legs[1].direction, Vec2::NEG_X);
+    }
+
+    #[test]
+    fn reflect_mirrors_across_the_normal() {
+        let reflected = reflect(Dir2::new(Vec2::new(1.0, -1.0)).unwrap(), Vec2::Y).unwrap();
+        assert_near(*reflected, Vec2::new(1.0, 1.0).normalize());
+    }
+
+    #[test]
+    fn bounds_follow_the_expanded_field() {
+        let inner = Vec2::new(1920.0, 1080.0);
+        for (expanded, half_size) in [
+            (false, Vec2::new(960.0, 540.0)),
+            (true, Vec2::new(1280.0, 720.0)),
+        ] {
+            assert_near(field_half_size(inner, expanded), half_size);
+            let walls = bounds(field_half_size(inner, expanded));
+            let inside = half_size - Vec2::splat(20.0);
+            let (position, _) = resolve(inside, Vec2::ZERO, 10.0, walls.iter());
+            assert_near(position, inside);
+            let (position, velocity) =
+                resolve(half_size + Vec2::splat(5.0), Vec2::ONE, 10.0, walls.iter());
+            assert_near(position, half_size - Vec2::splat(10.0));
+            assert_near(velocity, Vec2::ZERO);
+        }
+    }
+}
@@ -0,0 +1,395 @@
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use crate::bushido::affix::armored;
+use crate::bushido::affix::vengeful;
+use crate::bushido::enemy::finish_him;
+use crate::bushido::enemy::Enemy;
+use crate::bushido::enemy::EnemyHit;
+use crate::bushido::locale::Localizer;
+use crate::bushido::player::Finish;
+use crate::bushido::player::Player;
+use crate::bushido::player::PlayerAction;
+use crate::bushido::player::PlayerHealth;
+use crate::bushido::sound::SoundId;
+use crate::bushido::ActionState;
+use crate::bushido::GameState;
+use crate::bushido::Physical;
+use crate::bushido::PlayPhase;
+use crate::bushido::Sound;
+use crate::GameGlobal;
+use bevy::prelude::*;
+use rand::Rng;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+pub const DRAFT_OFFERS: usize = 3;
+pub const CHAIN_RADIUS: f32 = 120.0;
+
+pub struct DraftPlugin;
+impl Plugin for DraftPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Draft::default())
+            .insert_resource(Upgrades::default())
+            .add_systems(Startup, setup_draft_summary)
+            .add_systems(OnEnter(GameState::Play), seed_draft)
+            .add_systems(OnEnter(PlayPhase::Draft), offer_upgrades)
+            .add_systems(OnExit(PlayPhase::Draft), remove_offers)
+            .add_systems(Update, pick_upgrade.run_if(in_state(PlayPhase::Draft)))
+            .add_systems(
+                Update,
+                (
+                    slow_masks,
+                    chain_finisher
+                        .before(armored)
+                        .before(vengeful)
+                        .before(finish_him),
+                )
+                    .run_if(in_state(PlayPhase::Fighting)),
+            )
+            .add_systems(Update, update_draft_summary)
+            .add_systems(OnExit(GameState::GameOver), clear_upgrades);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Upgrade {
+    WideSlash,
+    SecondCharge,
+    ChainFinisher,
+    SlowMasks,
+    ExtraHealth,
+}
+
+impl Upgrade {
+    const POOL: [Upgrade; 5] = [
+        Upgrade::WideSlash,
+        Upgrade::SecondCharge,
+        Upgrade::ChainFinisher,
+        Upgrade::SlowMasks,
+        Upgrade::ExtraHealth,
+    ];
+
+    fn weight(&self) -> f32 {
+        match self {
+            Upgrade::WideSlash => 3.0,
+            Upgrade::SecondCharge => 1.0,
+            Upgrade::ChainFinisher => 2.0,
+            Upgrade::SlowMasks => 3.0,
+            Upgrade::ExtraHealth => 2.0,
+        }
+    }
+
+    pub fn glyph(&self) -> &'static str {
+        match self {
+            Upgrade::WideSlash => "広",
+            Upgrade::SecondCharge => "双",
+            Upgrade::ChainFinisher => "連",
+            Upgrade::SlowMasks => "鈍",
+            Upgrade::ExtraHealth => "命",
+        }
+    }
+
+    /// The locale key for what it does.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Upgrade::WideSlash => "upgrade-wide-slash",
+            Upgrade::SecondCharge => "upgrade-second-charge",
+            Upgrade::ChainFinisher => "upgrade-chain-finisher",
+            Upgrade::SlowMasks => "upgrade-slow-masks",
+            Upgrade::ExtraHealth => "upgrade-extra-health",
+        }
+    }
+}
+
+/// Upgrades picked so far this run.
+#[derive(Resource, Default)]
+pub struct Upgrades {
+    pub picks: Vec<Upgrade>,
+}
+
+impl Upgrades {
+    pub fn stacks(&self, upgrade: Upgrade) -> u32 {
+        self.picks.iter().filter(|pick| **pick == upgrade).count() as u32
+    }
+}
+
+/// The optional intermission between waves. Offers come from their own generator seeded at the
+/// start of the run, so a daily seed always deals the same hands no matter how the fights go.
+#[derive(Resource)]
+pub struct Draft {
+    pub enabled: bool,
+    pub daily: bool,
+    rand: Pcg32,
+    drafted_wave: i32,
+    offers: Vec<Upgrade>,
+    selected: usize,
+    stick_ready: bool,
+}
+
+impl Default for Draft {
+    fn default() -> Draft {
+        Draft {
+            enabled: false,
+            daily: false,
+            rand: Pcg32::from_entropy(),
+            drafted_wave: -1,
+            offers: Vec::new(),
+            selected: 0,
+            stick_ready: true,
+        }
+    }
+}
+
+impl Draft {
+    /// Whether clearing `wave` should stop for a draft. Only ever true once per wave.
+    pub fn wants_offer(&mut self, wave: i32) -> bool {
+        if !self.enabled || wave < 0 || self.drafted_wave >= wave {
+            return false;
+        }
+        self.drafted_wave = wave;
+        true
+    }
+
+    fn roll_offers(&mut self) -> Vec<Upgrade> {
+        let mut pool: Vec<Upgrade> = Upgrade::POOL.to_vec();
+        let mut offers = Vec::new();
+        while offers.len() < DRAFT_OFFERS && !pool.is_empty() {
+            let total: f32 = pool.iter().map(|upgrade| upgrade.weight()).sum();
+            let mut roll = self.rand.gen::<f32>() * total;
+            let mut pick = pool.len() - 1;
+            for (index, upgrade) in pool.iter().enumerate() {
+                if roll < upgrade.weight() {
+                    pick = index;
+                    break;
+                }
+                roll -= upgrade.weight();
+            }
+            offers.push(pool.remove(pick));
+        }
+        offers
+    }
+}
+
+fn seed_draft(mut draft: ResMut<Draft>) {
+    draft.drafted_wave = -1;
+    draft.rand = if draft.daily {
+        let day = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs() / 86400);
+        Pcg32::seed_from_u64(day)
+    } else {
+        Pcg32::from_entropy()
+    };
+}
+
+#[derive(Component)]
+struct DraftOffer {
+    index: usize,
+}
+
+fn offer_upgrades(mut commands: Commands, localizer: Localizer, mut draft: ResMut<Draft>) {
+    draft.offers = draft.roll_offers();
+    draft.selected = 0;
+    let count = draft.offers.len();
+    for (index, upgrade) in draft.offers.iter().enumerate() {
+        let x = (index as f32 - (count as f32 - 1.0) / 2.0) * 500.0;
+        let name = localizer.get(upgrade.name());
+        commands.spawn((
+            DraftOffer { index },
+            Text2dBundle {
+                text: Text::from_sections([
+                    TextSection::new(
+                        format!("{}\n", upgrade.glyph()),
+                        TextStyle {
+                            font: localizer.font(upgrade.glyph()),
+                            font_size: 160.0,
+                            color: Color::srgb(2.5, 0.25, 0.25),
+                            ..default()
+                        },
+                    ),
+                    TextSection::new(
+                        name.clone(),
+                        TextStyle {
+                            font: localizer.font(&name),
+                            font_size: 35.0,
+                            color: Color::srgb(2.5, 0.25, 0.25),
+                            ..default()
+                        },
+                    ),
+                ])
+                .with_justify(JustifyText::Center),
+                transform: Transform::from_xyz(x, 0.0, 60.0),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn pick_upgrade(
+    global: Res<GameGlobal>,
+    action_state: Res<ActionState<PlayerAction>>,
+    mut draft: ResMut<Draft>,
+    mut upgrades: ResMut<Upgrades>,
+    mut sound: EventWriter<Sound>,
+    mut next_phase: ResMut<NextState<PlayPhase>>,
+    mut offers: Query<(&DraftOffer, &Transform, &mut Text)>,
+    mut player: Query<&mut PlayerHealth, With<Player>>,
+) {
+    if draft.offers.is_empty() {
+        next_phase.set(PlayPhase::Fighting);
+        return;
+    }
+
+    if global.gamepad {
+        let stick = action_state.clamped_axis_pair(&PlayerAction::Run).x;
+        if draft.stick_ready && f32::abs(stick) > 0.5 {
+            draft.stick_ready = false;
+            draft.selected = if stick > 0.0 {
+                usize::min(draft.selected + 1, draft.offers.len() - 1)
+            } else {
+                draft.selected.saturating_sub(1)
+            };
+        } else if f32::abs(stick) < 0.2 {
+            draft.stick_ready = true;
+        }
+    } else if let Some((offer, _, _)) = offers.iter().min_by(|(_, a, _), (_, b, _)| {
+        f32::abs(a.translation.x - global.cursor_position.x)
+            .total_cmp(&f32::abs(b.translation.x - global.cursor_position.x))
+    }) {
+        draft.selected = offer.index;
+    }
+
+    for (offer, _, mut text) in offers.iter_mut() {
+        let brightness = if offer.index == draft.selected {
+            1.0
+        } else {
+            0.3
+        };
+        for section in text.sections.iter_mut() {
+            section.style.color.set_alpha(brightness);
+        }
+    }
+
+    if action_state.just_pressed(&PlayerAction::Slash) {
+        let upgrade = draft.offers[draft.selected];
+        upgrades.picks.push(upgrade);
+        if upgrade == Upgrade::ExtraHealth {
+            if let Ok(mut health) = player.get_single_mut() {
+                health.raise_max(1);
+            }
+        }
+        sound.send(Sound {
+            id: SoundId::Finish,
+            position: Vec3::ZERO,
+            speed: 1.0,
+        });
+        next_phase.set(PlayPhase::Fighting);
+    }
+}
+
+fn remove_offers(
+    mut commands: Commands,
+    mut draft: ResMut<Draft>,
+    offers: Query<Entity, With<DraftOffer>>,
+) {
+    draft.offers.clear();
+    for offer in offers.iter() {
+        commands.entity(offer).despawn_recursive();
+    }
+}
+
+fn slow_masks(upgrades: Res<Upgrades>, mut enemies: Query<&mut Physical, Added<Enemy>>) {
+    let slow = f32::powi(0.85, upgrades.stacks(Upgrade::SlowMasks) as i32);
+    for mut physical in enemies.iter_mut() {
+        physical.top_speed *= slow;
+    }
+}
+
+/// Each finished mask passes the finisher on to the closest unmarked mask nearby, once per stack.
+fn chain_finisher(
+    mut commands: Commands,
+    upgrades: Res<Upgrades>,
+    mut finish_events: EventReader<Finish>,
+    enemies: Query<(Entity, &Transform, Has<EnemyHit>), With<Enemy>>,
+) {
+    let links = upgrades.stacks(Upgrade::ChainFinisher);
+    if finish_events.read().count() == 0 || links == 0 {
+        return;
+    }
+    let mut marked: Vec<(Entity, Vec2)> = enemies
+        .iter()
+        .filter(|(_, _, hit)| *hit)
+        .map(|(entity, transform, _)| (entity, transform.translation.truncate()))
+        .collect();
+    let mut from: Vec<Vec2> = marked.iter().map(|(_, position)| *position).collect();
+    for _ in 0..links {
+        let mut next = Vec::new();
+        for position in from.iter() {
+            let closest = enemies
+                .iter()
+                .filter(|(entity, _, _)| !marked.iter().any(|(m, _)| m == entity))
+                .map(|(entity, transform, _)| (entity, transform.translation.truncate()))
+                .filter(|(_, other)| other.distance(*position) < CHAIN_RADIUS)
+                .min_by(|(_, a), (_, b)| a.distance(*position).total_cmp(&b.distance(*position)));
+            if let Some((entity, other)) = closest {
+                commands.entity(entity).insert(EnemyHit);
+                marked.push((entity, other));
+                next.push(other);
+            }
+        }
+        from = next;
+    }
+}
+
+fn clear_upgrades(mut upgrades: ResMut<Upgrades>) {
+    upgrades.picks.clear();
+}
+
+#[derive(Component)]
+struct DraftSummary;
+
+fn setup_draft_summary(mut commands: Commands) {
+    commands.spawn((
+        DraftSummary,
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 35.0,
+                    color: Color::srgb(2.5, 0.25, 0.25),
+                    ..default()
+                },
+            )
+            .with_justify(JustifyText::Center),
+            transform: Transform::from_xyz(0.0, -410.0, 10.0),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    ));
+}
+
+/// Lists the run's picks under the kill count once the run is over.
+fn update_draft_summary(
+    localizer: Localizer,
+    state: Res<State<GameState>>,
+    upgrades: Res<Upgrades>,
+    mut summary: Query<(&mut Text, &mut Visibility), With<DraftSummary>>,
+) {
+    if summary.is_empty() {
+        return;
+    }
+    let (mut text, mut visibility) = summary.single_mut();
+    if *state.get() != GameState::GameOver || upgrades.picks.is_empty() {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Visible;
+    let picks = upgrades
+        .picks
+        .iter()
+        .map(|pick| localizer.get(pick.name()))
+        .collect::<Vec<_>>()
+        .join(" / ");
+    localizer.set(&mut text, picks);
+}
@@ -1,18 +1,45 @@
 #![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use crate::bushido::accessibility::REDUCED_ATTACK_FADE;
+use crate::bushido::affix::insert_affixes;
+use crate::bushido::affix::roll_affixes;
+use crate::bushido::affix::AffixRolls;
+use crate::bushido::affix::Affixes;
+use crate::bushido::affix::Cursed;
+use crate::bushido::assets::GameAssets;
+use crate::bushido::broadphase::SpatialHash;
+use crate::bushido::broadphase::CELL_SIZE;
+use crate::bushido::collision::segments_cross;
+use crate::bushido::collision::sweep;
+use crate::bushido::collision::Shape;
+use crate::bushido::collision::Wall;
+use crate::bushido::collision::WallKind;
+use crate::bushido::draft::Draft;
+use crate::bushido::locale::Localizer;
 use crate::bushido::player::Finish;
+use crate::bushido::player::Noise;
 use crate::bushido::player::Player;
 use crate::bushido::player::PlayerCooldowns;
 use crate::bushido::player::PlayerHit;
 use crate::bushido::player::Slash;
+use crate::bushido::player::SLASH_COOLDOWN;
 use crate::bushido::player::SLASH_DISTANCE;
+use crate::bushido::player::SLASH_PAUSE;
+use crate::bushido::player::SLASH_TIP_REACH;
+use crate::bushido::player::SLASH_WIDTH;
+use crate::bushido::settings::Settings;
+use crate::bushido::sound::Mixed;
+use crate::bushido::sound::SoundId;
+use crate::bushido::update_colliders;
 use crate::bushido::Animate;
+use crate::bushido::DeathCause;
 use crate::bushido::GameState;
 use crate::bushido::Physical;
+use crate::bushido::PlayPhase;
+use crate::bushido::PlayerDied;
 use crate::bushido::Sound;
 use crate::bushido::SpriteAnimator;
 use crate::set_up_windows;
 use crate::GameGlobal;
-use bevy::math::bounding::Aabb2d;
 use bevy::math::bounding::BoundingCircle;
 use bevy::math::bounding::IntersectsVolume;
 use bevy::math::bounding::RayCast2d;
@@ -22,10 +49,19 @@ use bevy::utils::Duration;
 use rand::Rng;
 use std::f32::consts::PI;
 
+pub const MIRROR_TELEGRAPH: f32 = 0.6;
+pub const CLASH_WINDOW: f32 = 0.25;
+pub const CLASH_KNOCKBACK: f32 = 6.0;
+pub const PARRY_KNOCKBACK: f32 = 8.0;
+pub const PARRY_STAGGER: f32 = 1.2;
+
 pub struct EnemyPlugin;
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(SpawnWaves::default())
+            .init_resource::<LastPlayerSlash>()
+            .init_resource::<SpatialHash>()
+            .init_resource::<DarkPresenceConfig>()
             .add_systems(
                 Startup,
                 (setup_wall_lines, setup_dark_presence).after(set_up_windows),
@@ -36,37 +72,77 @@ impl Plugin for EnemyPlugin {
                     update_enemy,
                     spawn_waves,
                     spawn_enemies,
-                    enemy_collisions,
-                    player_collisions,
-                    hit_by_slash.before(player_collisions),
+                    rebuild_broadphase.after(update_colliders),
+                    enemy_collisions.after(rebuild_broadphase),
+                    player_collisions.after(rebuild_broadphase),
+                    hit_by_slash
+                        .after(rebuild_broadphase)
+                        .before(player_collisions),
+                    recover_from_stagger,
                     finish_him,
                     enemy_sprite_states,
                     update_dark_presence,
+                    update_dark_hunter.after(update_dark_presence),
+                    reset_dark_presence.before(update_dark_presence),
+                    dark_presence_warning.after(update_dark_presence),
+                    update_mirrors.after(update_enemy),
+                    mirror_clash.before(hit_by_slash),
+                    mirror_sprite_states,
+                    draw_mirror_telegraphs,
                 )
-                    .run_if(in_state(GameState::Play)),
+                    .run_if(in_state(PlayPhase::Fighting)),
             )
             .add_event::<SpawnEnemy>()
+            .add_event::<EnemyKilled>()
+            .add_event::<DarkPresenceReset>()
             // .add_systems(OnEnter(GameState::Play), spawn_one_dummy)
-            .add_systems(OnExit(GameState::Play), spawn_waves_reset)
-            .add_systems(OnExit(GameState::GameOver), destroy_enemies)
+            .add_systems(
+                OnExit(GameState::Play),
+                (spawn_waves_reset, hide_dark_presence_warning),
+            )
+            .add_systems(
+                OnExit(GameState::GameOver),
+                (destroy_enemies, destroy_dark_hunter, dark_presence_remove),
+            )
             .add_systems(OnEnter(GameState::DarkPresenceAttack), dark_presence_attack)
             .add_systems(
                 Update,
                 dark_presence_attack_timer.run_if(in_state(GameState::DarkPresenceAttack)),
             )
             .add_systems(
-                OnExit(GameState::DarkPresenceAttack),
-                (destroy_enemies, dark_presence_remove),
+                Update,
+                fade_dark_presence_attack.run_if(in_state(GameState::GameOver)),
             );
     }
 }
 
 #[derive(Resource)]
-struct SpawnWaves {
-    current: i32,
+pub struct SpawnWaves {
+    pub current: i32,
     skip: bool,
     timer: Stopwatch,
-    waves: Vec<Vec<EnemyType>>,
+    waves: Vec<Wave>,
+}
+
+/// One scripted wave: the enemies in it, and the affixes each of its masks spawns with on top of
+/// whatever it rolls.
+struct Wave {
+    enemies: Vec<EnemyType>,
+    affixes: Affixes,
+}
+
+impl Wave {
+    fn new(enemies: Vec<EnemyType>) -> Wave {
+        Wave {
+            enemies,
+            affixes: Affixes::empty(),
+        }
+    }
+
+    fn with_affixes(mut self, affixes: Affixes) -> Wave {
+        self.affixes = affixes;
+        self
+    }
 }
 
 impl Default for SpawnWaves {
@@ -76,30 +152,34 @@ impl Default for SpawnWaves {
             skip: false,
             timer: Stopwatch::new(),
             waves: vec![
-                vec![EnemyType::Dummy],
-                vec![EnemyType::Dummy; 2],
-                vec![EnemyType::GrayMask],
-                vec![EnemyType::GrayMask; 3],
-                vec![EnemyType::BlueMask],
-                vec![EnemyType::BlueMask; 3],
-                vec![
+                Wave::new(vec![EnemyType::Dummy]),
+                Wave::new(vec![EnemyType::Dummy; 2]),
+                Wave::new(vec![EnemyType::GrayMask]),
+                Wave::new(vec![EnemyType::GrayMask; 3]),
+                Wave::new(vec![EnemyType::BlueMask]),
+                Wave::new(vec![EnemyType::BlueMask; 3]),
+                Wave::new(vec![
                     EnemyType::GrayMask,
                     EnemyType::BlueMask,
                     EnemyType::GrayMask,
                     EnemyType::BlueMask,
                     EnemyType::GrayMask,
                     EnemyType::BlueMask,
-                ],
-                vec![EnemyType::GrayMask, EnemyType::GrayMask, EnemyType::RedMask],
-                vec![
+                ]),
+                Wave::new(vec![
+                    EnemyType::GrayMask,
+                    EnemyType::GrayMask,
+                    EnemyType::RedMask,
+                ]),
+                Wave::new(vec![
                     EnemyType::GrayMask,
                     EnemyType::GrayMask,
                     EnemyType::RedMask,
                     EnemyType::GrayMask,
                     EnemyType::GrayMask,
                     EnemyType::RedMask,
-                ],
-                vec![
+                ]),
+                Wave::new(vec![
                     EnemyType::GrayMask,
                     EnemyType::BlueMask,
                     EnemyType::RedMask,
@@ -109,15 +189,22 @@ impl Default for SpawnWaves {
                     EnemyType::GrayMask,
                     EnemyType::BlueMask,
                     EnemyType::RedMask,
-                ],
-                vec![EnemyType::BlackMask],
-                vec![
+                ]),
+                Wave::new(vec![EnemyType::BlackMask]),
+                Wave::new(vec![
                     EnemyType::BlackMask,
                     EnemyType::BlackMask,
                     EnemyType::BlackMask,
                     EnemyType::BlackMask,
-                ],
-                vec![
+                ]),
+                Wave::new(vec![EnemyType::MirrorSamurai]),
+                Wave::new(vec![
+                    EnemyType::GrayMask,
+                    EnemyType::MirrorSamurai,
+                    EnemyType::GrayMask,
+                    EnemyType::MirrorSamurai,
+                ]),
+                Wave::new(vec![
                     EnemyType::RedMask,
                     EnemyType::BlackMask,
                     EnemyType::RedMask,
@@ -127,8 +214,9 @@ impl Default for SpawnWaves {
                     EnemyType::RedMask,
                     EnemyType::BlackMask,
                     EnemyType::RedMask,
-                ],
-                vec![
+                ])
+                .with_affixes(Affixes::ARMORED),
+                Wave::new(vec![
                     EnemyType::BlueMask,
                     EnemyType::BlackMask,
                     EnemyType::BlueMask,
@@ -138,7 +226,7 @@ impl Default for SpawnWaves {
                     EnemyType::BlueMask,
                     EnemyType::BlackMask,
                     EnemyType::BlueMask,
-                ],
+                ]),
             ],
         }
     }
@@ -152,23 +240,33 @@ fn spawn_waves_reset(mut spawn_waves: ResMut<SpawnWaves>) {
 fn spawn_waves(
     mut global: ResMut<GameGlobal>,
     mut spawn_waves: ResMut<SpawnWaves>,
+    affix_rolls: Res<AffixRolls>,
+    mut draft: ResMut<Draft>,
+    mut next_phase: ResMut<NextState<PlayPhase>>,
     mut new_enemy: EventWriter<SpawnEnemy>,
     enemies: Query<&Enemy>,
     player: Query<&Transform, With<Player>>,
 ) {
     if enemies.is_empty() {
         if !spawn_waves.skip {
+            if draft.wants_offer(spawn_waves.current) {
+                next_phase.set(PlayPhase::Draft);
+                return;
+            }
             spawn_waves.current += 1;
             let mut wave = Vec::new();
+            let mut forced = Affixes::empty();
             if spawn_waves.current < spawn_waves.waves.len() as i32 - 1 {
-                for pick in &spawn_waves.waves[spawn_waves.current as usize] {
-                    wave.push(*pick);
-                }
+                let scripted = &spawn_waves.waves[spawn_waves.current as usize];
+                wave.extend(scripted.enemies.iter().copied());
+                forced = scripted.affixes;
             } else {
                 for n in 0..spawn_waves.current {
                     let rand = global.rand.gen::<f32>();
                     let pick;
-                    if rand < 0.1 {
+                    if rand < 0.05 {
+                        pick = EnemyType::MirrorSamurai;
+                    } else if rand < 0.1 {
                         pick = EnemyType::BlackMask;
                     } else if rand < 0.3 {
                         pick = EnemyType::RedMask;
@@ -216,9 +314,21 @@ fn spawn_waves(
                 let mut pos =
                     direction.rotate(Vec2::from_angle(delta_angle * current_enemy as f32));
                 pos.x *= 1.35;
+                // Only the regular masks roll affixes. The dummy is for practice and the mirror
+                // has its own rules.
+                let affixes = match enemy_type {
+                    EnemyType::GrayMask
+                    | EnemyType::BlueMask
+                    | EnemyType::RedMask
+                    | EnemyType::BlackMask => {
+                        forced | roll_affixes(&mut global.rand, spawn_waves.current, &affix_rolls)
+                    }
+                    EnemyType::Dummy | EnemyType::MirrorSamurai => Affixes::empty(),
+                };
                 new_enemy.send(SpawnEnemy {
                     enemy_type: wave[current_enemy],
                     position: pos,
+                    affixes,
                 });
                 current_enemy += 1;
             }
@@ -232,6 +342,7 @@ fn spawn_one_dummy(mut new_enemy: EventWriter<SpawnEnemy>) {
     new_enemy.send(SpawnEnemy {
         enemy_type: EnemyType::Dummy,
         position: (-60.0, 0.0).into(),
+        affixes: Affixes::empty(),
     });
 }
 
@@ -241,10 +352,10 @@ struct EnemySpawner {
 }
 
 #[derive(Component, Default)]
-struct Enemy;
+pub struct Enemy;
 
 #[derive(Component)]
-struct EnemySprite;
+pub struct EnemySprite;
 
 #[derive(Clone, Copy)]
 enum EnemyType {
@@ -253,6 +364,7 @@ enum EnemyType {
     BlueMask,
     RedMask,
     BlackMask,
+    MirrorSamurai,
 }
 
 #[derive(Component, Default)]
@@ -270,22 +382,59 @@ struct RedMask;
 #[derive(Component, Default)]
 struct BlackMask;
 
+#[derive(PartialEq)]
+enum MirrorStance {
+    Stalking,
+    Telegraph,
+    Pause,
+}
+
+/// Uses the player's own kit: a cooldown, a telegraphed teleport slash and the pause after it.
+#[derive(Component)]
+struct MirrorSamurai {
+    stance: MirrorStance,
+    cooldown: Timer,
+    telegraph: Timer,
+    pause: Timer,
+    direction: Dir2,
+    last_slash: Option<(Vec2, Vec2)>,
+    window: Timer,
+}
+
+impl Default for MirrorSamurai {
+    fn default() -> MirrorSamurai {
+        MirrorSamurai {
+            stance: MirrorStance::Stalking,
+            cooldown: Timer::from_seconds(SLASH_COOLDOWN, TimerMode::Once),
+            telegraph: Timer::from_seconds(MIRROR_TELEGRAPH, TimerMode::Once),
+            pause: Timer::from_seconds(SLASH_PAUSE, TimerMode::Once),
+            direction: Dir2::X,
+            last_slash: None,
+            window: Timer::from_seconds(CLASH_WINDOW, TimerMode::Once),
+        }
+    }
+}
+
+#[derive(Component)]
+struct MirrorSprite;
+
 #[derive(Event)]
 struct SpawnEnemy {
     enemy_type: EnemyType,
     position: Vec2,
+    affixes: Affixes,
 }
 
 fn spawn_enemies(
     mut commands: Commands,
     mut events: EventReader<SpawnEnemy>,
-    asset_server: Res<AssetServer>,
-    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    assets: Res<GameAssets>,
 ) {
     for event in events.read() {
+        let enemy;
         match event.enemy_type {
             EnemyType::Dummy => {
-                commands
+                enemy = commands
                     .spawn((
                         Enemy,
                         Dummy,
@@ -303,19 +452,13 @@ fn spawn_enemies(
                             EnemySprite,
                             SpriteAnimator {
                                 sprite: SpriteBundle {
-                                    texture: asset_server.load("embedded://Dummy.png"),
+                                    texture: assets.dummy.clone(),
                                     transform: Transform::from_scale(Vec3::splat(2.0))
                                         .with_translation(Vec3::new(0.0, 0.0, 0.0)),
                                     ..default()
                                 },
                                 layout: TextureAtlas {
-                                    layout: layouts.add(TextureAtlasLayout::from_grid(
-                                        UVec2::splat(20),
-                                        1,
-                                        1,
-                                        None,
-                                        None,
-                                    )),
+                                    layout: assets.dummy_layout.clone(),
                                     index: 0,
                                 },
                                 animation: Animate {
@@ -328,10 +471,11 @@ fn spawn_enemies(
                                 },
                             },
                         ));
-                    });
+                    })
+                    .id();
             }
             EnemyType::GrayMask => {
-                commands
+                enemy = commands
                     .spawn((
                         Enemy,
                         GrayMask,
@@ -355,19 +499,13 @@ fn spawn_enemies(
                             EnemySprite,
                             SpriteAnimator {
                                 sprite: SpriteBundle {
-                                    texture: asset_server.load("embedded://GrayMask.png"),
+                                    texture: assets.gray_mask.clone(),
                                     transform: Transform::from_scale(Vec3::splat(2.0))
                                         .with_translation(Vec3::new(0.0, 0.0, 0.0)),
                                     ..default()
                                 },
                                 layout: TextureAtlas {
-                                    layout: layouts.add(TextureAtlasLayout::from_grid(
-                                        UVec2::splat(16),
-                                        4,
-                                        1,
-                                        None,
-                                        None,
-                                    )),
+                                    layout: assets.mask_layout.clone(),
                                     index: 0,
                                 },
                                 animation: Animate {
@@ -380,10 +518,11 @@ fn spawn_enemies(
                                 },
                             },
                         ));
-                    });
+                    })
+                    .id();
             }
             EnemyType::BlueMask => {
-                commands
+                enemy = commands
                     .spawn((
                         Enemy,
                         BlueMask,
@@ -411,19 +550,13 @@ fn spawn_enemies(
                                         color: Color::srgb(4.0, 4.0, 4.0),
                                         ..default()
                                     },
-                                    texture: asset_server.load("embedded://BlueMask.png"),
+                                    texture: assets.blue_mask.clone(),
                                     transform: Transform::from_scale(Vec3::splat(2.0))
                                         .with_translation(Vec3::new(0.0, 0.0, 0.0)),
                                     ..default()
                                 },
                                 layout: TextureAtlas {
-                                    layout: layouts.add(TextureAtlasLayout::from_grid(
-                                        UVec2::splat(16),
-                                        4,
-                                        1,
-                                        None,
-                                        None,
-                                    )),
+                                    layout: assets.mask_layout.clone(),
                                     index: 0,
                                 },
                                 animation: Animate {
@@ -436,10 +569,11 @@ fn spawn_enemies(
                                 },
                             },
                         ));
-                    });
+                    })
+                    .id();
             }
             EnemyType::RedMask => {
-                commands
+                enemy = commands
                     .spawn((
                         Enemy,
                         RedMask,
@@ -467,19 +601,13 @@ fn spawn_enemies(
                                         color: Color::srgb(4.0, 4.0, 4.0),
                                         ..default()
                                     },
-                                    texture: asset_server.load("embedded://RedMask.png"),
+                                    texture: assets.red_mask.clone(),
                                     transform: Transform::from_scale(Vec3::splat(2.0))
                                         .with_translation(Vec3::new(0.0, 0.0, 0.0)),
                                     ..default()
                                 },
                                 layout: TextureAtlas {
-                                    layout: layouts.add(TextureAtlasLayout::from_grid(
-                                        UVec2::splat(16),
-                                        4,
-                                        1,
-                                        None,
-                                        None,
-                                    )),
+                                    layout: assets.mask_layout.clone(),
                                     index: 0,
                                 },
                                 animation: Animate {
@@ -492,10 +620,11 @@ fn spawn_enemies(
                                 },
                             },
                         ));
-                    });
+                    })
+                    .id();
             }
             EnemyType::BlackMask => {
-                commands
+                enemy = commands
                     .spawn((
                         Enemy,
                         BlackMask,
@@ -523,19 +652,13 @@ fn spawn_enemies(
                                         color: Color::srgb(4.0, 4.0, 4.0),
                                         ..default()
                                     },
-                                    texture: asset_server.load("embedded://BlackMask.png"),
+                                    texture: assets.black_mask.clone(),
                                     transform: Transform::from_scale(Vec3::splat(2.0))
                                         .with_translation(Vec3::new(0.0, 0.0, 0.0)),
                                     ..default()
                                 },
                                 layout: TextureAtlas {
-                                    layout: layouts.add(TextureAtlasLayout::from_grid(
-                                        UVec2::splat(16),
-                                        4,
-                                        1,
-                                        None,
-                                        None,
-                                    )),
+                                    layout: assets.mask_layout.clone(),
                                     index: 0,
                                 },
                                 animation: Animate {
@@ -548,9 +671,90 @@ fn spawn_enemies(
                                 },
                             },
                         ));
-                    });
+                    })
+                    .id();
+            }
+            EnemyType::MirrorSamurai => {
+                enemy = commands
+                    .spawn((
+                        Enemy,
+                        MirrorSamurai::default(),
+                        SpatialBundle {
+                            transform: Transform::from_xyz(event.position.x, event.position.y, 0.0),
+                            ..default()
+                        },
+                        Physical {
+                            velocity: Vec2::splat(0.0),
+                            acceleration: 2.0,
+                            deceleration: 1.0,
+                            top_speed: 0.9,
+                            quantize: 0.0,
+                            collider: BoundingCircle::new(Vec2::ZERO, 15.0),
+                            wall_padding: 6.0,
+                            ..default()
+                        },
+                    ))
+                    .with_children(|commands| {
+                        commands.spawn((
+                            EnemySprite,
+                            MirrorSprite,
+                            SpriteAnimator {
+                                sprite: SpriteBundle {
+                                    sprite: Sprite {
+                                        color: Color::srgb(4.0, 0.5, 0.5),
+                                        ..default()
+                                    },
+                                    texture: assets.player_top.clone(),
+                                    transform: Transform::from_scale(Vec3::splat(2.0))
+                                        .with_translation(Vec3::new(0.0, 0.0, 2.0)),
+                                    ..default()
+                                },
+                                layout: TextureAtlas {
+                                    layout: assets.player_layout.clone(),
+                                    index: 0,
+                                },
+                                animation: Animate {
+                                    first: 0,
+                                    last: 1,
+                                    speed: 3.0,
+                                    offset: 0.0,
+                                    timer: Timer::from_seconds(0.001, TimerMode::Once),
+                                    current: 0,
+                                },
+                            },
+                        ));
+                        commands.spawn((
+                            EnemySprite,
+                            SpriteAnimator {
+                                sprite: SpriteBundle {
+                                    sprite: Sprite {
+                                        color: Color::srgb(0.5, 0.1, 0.1),
+                                        ..default()
+                                    },
+                                    texture: assets.player_bottom.clone(),
+                                    transform: Transform::from_scale(Vec3::splat(2.0))
+                                        .with_translation(Vec3::new(0.0, 0.0, 1.0)),
+                                    ..default()
+                                },
+                                layout: TextureAtlas {
+                                    layout: assets.player_layout.clone(),
+                                    index: 0,
+                                },
+                                animation: Animate {
+                                    first: 1,
+                                    last: 4,
+                                    speed: 6.0,
+                                    offset: 0.0,
+                                    timer: Timer::from_seconds(0.001, TimerMode::Once),
+                                    current: 0,
+                                },
+                            },
+                        ));
+                    })
+                    .id();
             }
         };
+        insert_affixes(&mut commands.entity(enemy), event.affixes);
     }
 }
 
@@ -566,6 +770,7 @@ fn update_enemy(
             Option<&BlueMask>,
             Option<&RedMask>,
             Option<&BlackMask>,
+            Has<Staggered>,
         ),
         With<Enemy>,
     >,
@@ -580,8 +785,16 @@ fn update_enemy(
     let player_inbounds = f32::abs(player.translation.x) < global.inner_world_size.x / 2.0
         && f32::abs(player.translation.y) < global.inner_world_size.y / 2.0;
 
-    for (mut transform, mut physical, dummy, gray_mask, blue_mask, red_mask, black_mask) in
-        enemies.iter_mut()
+    for (
+        mut transform,
+        mut physical,
+        dummy,
+        gray_mask,
+        blue_mask,
+        red_mask,
+        black_mask,
+        staggered,
+    ) in enemies.iter_mut()
     {
         let enemy_inbounds = f32::abs(transform.translation.x) < global.inner_world_size.x / 2.0
             && f32::abs(transform.translation.y) < global.inner_world_size.y / 2.0;
@@ -591,7 +804,7 @@ fn update_enemy(
             player.translation.y - transform.translation.y,
         ]);
         let mut direction = player_vector.normalize_or_zero();
-        if player_inbounds == enemy_inbounds {
+        if player_inbounds == enemy_inbounds && !staggered {
             if gray_mask.is_some() {
                 physical.accelerate(delta * direction);
             }
@@ -646,13 +859,31 @@ fn enemy_sprite_states(
     }
 }
 
-fn enemy_collisions(time: Res<Time>, mut enemies: Query<(&mut Physical, &Transform), With<Enemy>>) {
+fn rebuild_broadphase(
+    mut broadphase: ResMut<SpatialHash>,
+    enemies: Query<(Entity, &Physical), With<Enemy>>,
+) {
+    broadphase.clear();
+    for (entity, physical) in enemies.iter() {
+        broadphase.insert(entity, physical.collider);
+    }
+}
+
+fn enemy_collisions(
+    time: Res<Time>,
+    broadphase: Res<SpatialHash>,
+    mut enemies: Query<(&mut Physical, &Transform), With<Enemy>>,
+) {
     let delta = time.delta_seconds();
-    let mut pairs = enemies.iter_combinations_mut();
-    while let Some(
-        [(mut enemy1_physical, enemy1_transform), (mut enemy2_physical, enemy2_transform)],
-    ) = pairs.fetch_next()
-    {
+    for (enemy1, enemy2) in broadphase.pairs(CELL_SIZE) {
+        let Ok([(mut enemy1_physical, enemy1_transform), (mut enemy2_physical, enemy2_transform)]) =
+            enemies.get_many_mut([enemy1, enemy2])
+        else {
+            continue;
+        };
+        if !enemy1_physical.separates || !enemy2_physical.separates {
+            continue;
+        }
         if enemy1_physical
             .collider
             .intersects(&enemy2_physical.collider)
@@ -664,7 +895,7 @@ fn enemy_collisions(time: Res<Time>, mut enemies: Query<(&mut Physical, &Transfo
         } else {
             let direction = enemy1_transform.translation - enemy2_transform.translation;
             let normal = direction.truncate().normalize();
-            if direction.length() < 100.0 {
+            if direction.length() < CELL_SIZE {
                 enemy1_physical.impulse(normal * delta * 0.25);
                 enemy2_physical.impulse(-normal * delta * 0.25);
             }
@@ -674,13 +905,27 @@ fn enemy_collisions(time: Res<Time>, mut enemies: Query<(&mut Physical, &Transfo
 
 fn player_collisions(
     time: Res<Time>,
+    mut commands: Commands,
     mut hit: EventWriter<PlayerHit>,
-    mut enemies: Query<(&mut Physical, &Transform, Option<&Dummy>), With<Enemy>>,
-    mut player: Query<(&mut Physical, &Transform), Without<Enemy>>,
+    mut sound: EventWriter<Sound>,
+    broadphase: Res<SpatialHash>,
+    mut enemies: Query<(Entity, &mut Physical, &Transform, Option<&Dummy>), With<Enemy>>,
+    mut player: Query<
+        (&mut Physical, &Transform, &mut PlayerCooldowns),
+        (With<Player>, Without<Enemy>),
+    >,
 ) {
     let delta = time.delta_seconds();
-    let (mut player_physical, player_transform) = player.single_mut();
-    for (mut enemy_physical, enemy_transform, dummy) in enemies.iter_mut() {
+    let (mut player_physical, player_transform, mut cooldowns) = player.single_mut();
+    let nearby = broadphase.query_circle(
+        player_physical.collider.center,
+        player_physical.collider.radius(),
+    );
+    for (entity, _) in nearby {
+        let Ok((entity, mut enemy_physical, enemy_transform, dummy)) = enemies.get_mut(entity)
+        else {
+            continue;
+        };
         if enemy_physical
             .collider
             .intersects(&player_physical.collider)
@@ -691,8 +936,25 @@ fn player_collisions(
                 enemy_physical.impulse(-normal * delta * 60.0);
                 player_physical.impulse(normal * delta * 60.0);
             }
-            if player_physical.hit_cooldown.finished() {
-                if enemy_physical.hit_cooldown.finished() {
+            if player_physical.hit_cooldown.finished() && !cooldowns.invulnerable() {
+                if enemy_physical.hit_cooldown.finished() && cooldowns.parrying() {
+                    // The contact is turned back on the attacker, which is left open to the next
+                    // finisher.
+                    cooldowns.parried = true;
+                    enemy_physical.impulse(-normal * PARRY_KNOCKBACK);
+                    enemy_physical.hit_cooldown.reset();
+                    commands.entity(entity).insert((
+                        EnemyHit,
+                        Staggered {
+                            timer: Timer::from_seconds(PARRY_STAGGER, TimerMode::Once),
+                        },
+                    ));
+                    sound.send(Sound {
+                        id: SoundId::Parry,
+                        position: enemy_transform.translation,
+                        speed: 1.0,
+                    });
+                } else if enemy_physical.hit_cooldown.finished() {
                     if !dummy.is_some() {
                         hit.send(PlayerHit);
                     }
@@ -705,53 +967,91 @@ fn player_collisions(
 }
 
 #[derive(Component)]
-struct Wall {
-    collider: Aabb2d,
+struct Staggered {
+    timer: Timer,
+}
+
+fn recover_from_stagger(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut enemies: Query<(Entity, &mut Staggered)>,
+) {
+    for (entity, mut staggered) in enemies.iter_mut() {
+        staggered.timer.tick(time.delta());
+        if staggered.timer.finished() {
+            commands.entity(entity).remove::<Staggered>();
+        }
+    }
 }
 
 fn setup_wall_lines(mut commands: Commands, global: Res<GameGlobal>) {
     let x_pos = global.inner_world_size.x / 2.0;
     let y_pos = global.inner_world_size.y / 2.0;
-    commands.spawn(Wall {
-        collider: Aabb2d::from_point_cloud(
-            (0.0, 0.0).into(),
-            0.0,
-            &[(-x_pos, -y_pos).into(), (x_pos, -y_pos).into()],
-        ),
-    });
-    commands.spawn(Wall {
-        collider: Aabb2d::from_point_cloud(
-            (0.0, 0.0).into(),
-            0.0,
-            &[(-x_pos, y_pos).into(), (x_pos, y_pos).into()],
-        ),
-    });
-    commands.spawn(Wall {
-        collider: Aabb2d::from_point_cloud(
-            (0.0, 0.0).into(),
-            0.0,
-            &[(-x_pos, -y_pos).into(), (-x_pos, y_pos).into()],
-        ),
-    });
-    commands.spawn(Wall {
-        collider: Aabb2d::from_point_cloud(
-            (0.0, 0.0).into(),
-            0.0,
-            &[(x_pos, -y_pos).into(), (x_pos, y_pos).into()],
-        ),
-    });
+    let corners = [
+        Vec2::new(-x_pos, -y_pos),
+        Vec2::new(x_pos, -y_pos),
+        Vec2::new(x_pos, y_pos),
+        Vec2::new(-x_pos, y_pos),
+    ];
+    for (index, start) in corners.iter().enumerate() {
+        commands.spawn(Wall {
+            shape: Shape::Segment {
+                start: *start,
+                end: corners[(index + 1) % corners.len()],
+            },
+            kind: WallKind::Border,
+        });
+    }
 }
 
 #[derive(Component)]
-struct EnemyHit;
+pub struct EnemyHit;
 
-fn hit_by_slash(
+/// The area a slash sweeps: everything within `radius` of the line from `start` to `end`. Enemies
+/// are also caught within `tip` of where it lands.
+#[derive(Clone, Copy, Debug)]
+pub struct SlashCapsule {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub radius: f32,
+    pub tip: f32,
+}
+
+impl SlashCapsule {
+    pub fn new(start: Vec2, direction: Dir2, length: f32, half_width: f32) -> SlashCapsule {
+        SlashCapsule {
+            start,
+            end: start + *direction * length,
+            radius: half_width,
+            tip: half_width + SLASH_TIP_REACH,
+        }
+    }
+
+    /// How far from the line anything it catches can be, for the broadphase.
+    pub fn reach(&self) -> f32 {
+        f32::max(self.radius, self.tip)
+    }
+
+    pub fn intersects(&self, shape: &Shape) -> bool {
+        shape.distance_to_segment(self.start, self.end) <= self.radius
+    }
+
+    pub fn intersects_circle(&self, circle: &BoundingCircle) -> bool {
+        self.intersects(&Shape::Circle {
+            center: circle.center,
+            radius: circle.radius(),
+        }) || circle.center.distance(self.end) <= self.tip + circle.radius()
+    }
+}
+
+pub fn hit_by_slash(
     mut commands: Commands,
     global: Res<GameGlobal>,
     mut player_cooldowns: Query<&mut PlayerCooldowns>,
     mut slash_events: EventReader<Slash>,
     mut sound: EventWriter<Sound>,
-    mut colliders: Query<(&mut Physical, Entity), With<Enemy>>,
+    broadphase: Res<SpatialHash>,
+    mut colliders: Query<(&mut Physical, Entity, Has<Cursed>), With<Enemy>>,
     walls: Query<&Wall>,
 ) {
     if player_cooldowns.is_empty() {
@@ -759,125 +1059,377 @@ fn hit_by_slash(
     }
     let mut cooldowns = player_cooldowns.single_mut();
     for line in slash_events.read() {
-        let slash_one = RayCast2d::new(
-            line.start
-                + Vec2::from_angle(PI / 2.0)
-                    .rotate(*line.direction)
-                    .normalize_or_zero()
-                    * 7.0,
-            line.direction,
-            line.length,
-        );
-        let slash_two = RayCast2d::new(
-            line.start
-                + Vec2::from_angle(3.0 * PI / 2.0)
-                    .rotate(*line.direction)
-                    .normalize_or_zero()
-                    * 7.0,
-            line.direction,
-            line.length,
-        );
-        let slash_end = BoundingCircle::new(
-            line.start + line.direction.normalize_or_zero() * line.length,
-            15.0,
-        );
-        for (mut physical, entity) in colliders.iter_mut() {
-            if (slash_one
-                .circle_intersection_at(&physical.collider)
-                .is_some()
-                || slash_two
-                    .circle_intersection_at(&physical.collider)
-                    .is_some()
-                || slash_end.intersects(&physical.collider))
-                && physical.hit_cooldown.finished()
-            {
+        let capsule = SlashCapsule::new(line.start, line.direction, line.length, line.width);
+        // Cutting a cursed mask takes away this slash's refund, from the hit and the wall alike.
+        let mut hit = false;
+        let mut cursed = false;
+        for (entity, _) in broadphase.query_segment(capsule.start, capsule.end, capsule.reach()) {
+            let Ok((mut physical, entity, is_cursed)) = colliders.get_mut(entity) else {
+                continue;
+            };
+            if capsule.intersects_circle(&physical.collider) && physical.hit_cooldown.finished() {
                 sound.send(Sound {
-                    name: "hit".to_string(),
+                    id: SoundId::Hit,
                     position: physical.collider.center.extend(0.0),
                     speed: 1.0,
                 });
                 cooldowns.pause.reset();
-                cooldowns.slash.set_elapsed(Duration::from_secs_f32(
-                    crate::bushido::player::SLASH_COOLDOWN,
-                ));
+                hit = true;
+                cursed |= is_cursed;
                 physical.hit_cooldown.reset();
                 commands.entity(entity).insert(EnemyHit);
             }
         }
-        for wall in walls.iter() {
-            if global.expanded && slash_one.aabb_intersection_at(&wall.collider).is_some()
-                || slash_two.aabb_intersection_at(&wall.collider).is_some()
-            {
-                sound.send(Sound {
-                    name: "vrrp".to_string(),
-                    position: line.start.extend(0.0),
-                    speed: 1.0,
-                });
-                cooldowns.pause.reset();
-                cooldowns.slash.set_elapsed(Duration::from_secs_f32(
-                    crate::bushido::player::SLASH_COOLDOWN,
-                ));
+        if hit && !cursed {
+            let refund = cooldowns.slash.duration();
+            cooldowns.slash.set_elapsed(refund);
+        }
+        if line.bounced || cursed {
+            continue;
+        }
+        if line.blocked
+            || walls.iter().any(|wall| {
+                wall.kind.refunds_slash(global.expanded) && capsule.intersects(&wall.shape)
+            })
+        {
+            sound.send(Sound {
+                id: SoundId::Vrrp,
+                position: line.start.extend(0.0),
+                speed: 1.0,
+            });
+            cooldowns.pause.reset();
+            let refund = cooldowns.slash.duration();
+            cooldowns.slash.set_elapsed(refund);
+        }
+    }
+}
+
+#[derive(Resource)]
+struct LastPlayerSlash {
+    line: Option<(Vec2, Vec2)>,
+    window: Timer,
+}
+
+impl Default for LastPlayerSlash {
+    fn default() -> LastPlayerSlash {
+        LastPlayerSlash {
+            line: None,
+            window: Timer::from_seconds(CLASH_WINDOW, TimerMode::Once),
+        }
+    }
+}
+
+fn update_mirrors(
+    time: Res<Time>,
+    global: Res<GameGlobal>,
+    mut last_player_slash: ResMut<LastPlayerSlash>,
+    mut hit: EventWriter<PlayerHit>,
+    mut sound: EventWriter<Sound>,
+    mut mirrors: Query<(&mut MirrorSamurai, &mut Transform, &mut Physical), With<Enemy>>,
+    mut player: Query<
+        (&mut Physical, &Transform, &PlayerCooldowns),
+        (With<Player>, Without<Enemy>),
+    >,
+    walls: Query<&Wall>,
+) {
+    if player.is_empty() {
+        return;
+    }
+    let delta = time.delta_seconds();
+    let (mut player_physical, player_transform, cooldowns) = player.single_mut();
+    last_player_slash.window.tick(time.delta());
+
+    for (mut mirror, mut transform, mut physical) in mirrors.iter_mut() {
+        mirror.window.tick(time.delta());
+        let player_vector = (player_transform.translation - transform.translation).truncate();
+        let direction = player_vector.normalize_or_zero();
+
+        match mirror.stance {
+            MirrorStance::Stalking => {
+                mirror.cooldown.tick(time.delta());
+                if player_vector.length() > SLASH_DISTANCE * 0.8 {
+                    physical.accelerate(delta * direction);
+                } else {
+                    physical.accelerate(-delta * direction);
+                }
+                if mirror.cooldown.finished() && player_vector.length() < SLASH_DISTANCE * 1.2 {
+                    mirror.direction = Dir2::new(direction).unwrap_or(Dir2::X);
+                    mirror.telegraph.reset();
+                    mirror.stance = MirrorStance::Telegraph;
+                }
+            }
+            MirrorStance::Telegraph => {
+                mirror.telegraph.tick(time.delta());
+                physical.velocity = Vec2::ZERO;
+                if mirror.telegraph.finished() {
+                    sound.send(Sound {
+                        id: SoundId::Slash,
+                        position: transform.translation,
+                        speed: 0.8,
+                    });
+                    // Stops short at walls the same way the player's slash does.
+                    let slash_start = transform.translation.truncate();
+                    let (slash_length, _) = sweep(
+                        slash_start,
+                        mirror.direction,
+                        SLASH_DISTANCE,
+                        physical.collider.radius() + physical.wall_padding,
+                        walls
+                            .iter()
+                            .filter(|wall| wall.kind.stops_slash(global.expanded))
+                            .map(|wall| &wall.shape),
+                    );
+                    let slash_end = slash_start + *mirror.direction * slash_length;
+                    transform.translation = slash_end.extend(transform.translation.z);
+                    physical.collider.center = slash_end;
+                    mirror.last_slash = Some((slash_start, slash_end));
+                    mirror.window.reset();
+                    mirror.pause.reset();
+                    mirror.cooldown.reset();
+                    mirror.stance = MirrorStance::Pause;
+
+                    let crossed = match last_player_slash.line {
+                        Some((start, end)) => {
+                            !last_player_slash.window.finished()
+                                && segments_cross(slash_start, slash_end, start, end)
+                        }
+                        None => false,
+                    };
+                    if crossed {
+                        last_player_slash.line = None;
+                        clash(
+                            &mut mirror,
+                            &mut physical,
+                            &mut player_physical,
+                            player_transform.translation - transform.translation,
+                            &mut sound,
+                        );
+                    } else if SlashCapsule::new(
+                        slash_start,
+                        mirror.direction,
+                        slash_length,
+                        SLASH_WIDTH,
+                    )
+                    .intersects_circle(&player_physical.collider)
+                        && player_physical.hit_cooldown.finished()
+                        && !cooldowns.invulnerable()
+                    {
+                        hit.send(PlayerHit);
+                        player_physical.hit_cooldown.reset();
+                    }
+                }
+            }
+            MirrorStance::Pause => {
+                mirror.pause.tick(time.delta());
+                physical.velocity = Vec2::ZERO;
+                if mirror.pause.finished() {
+                    mirror.stance = MirrorStance::Stalking;
+                }
+            }
+        }
+    }
+}
+
+/// A player slash crossing a mirror slash inside the window throws both apart and nobody is hit.
+fn mirror_clash(
+    mut slash_events: EventReader<Slash>,
+    mut last_player_slash: ResMut<LastPlayerSlash>,
+    mut sound: EventWriter<Sound>,
+    mut mirrors: Query<(&mut MirrorSamurai, &mut Physical, &Transform), With<Enemy>>,
+    mut player: Query<(&mut Physical, &Transform), (With<Player>, Without<Enemy>)>,
+) {
+    if player.is_empty() {
+        return;
+    }
+    let (mut player_physical, player_transform) = player.single_mut();
+    for line in slash_events.read() {
+        let line_end = line.start + *line.direction * line.length;
+        let mut clashed = false;
+        for (mut mirror, mut physical, transform) in mirrors.iter_mut() {
+            if let Some((start, end)) = mirror.last_slash {
+                if !mirror.window.finished() && segments_cross(line.start, line_end, start, end) {
+                    clash(
+                        &mut mirror,
+                        &mut physical,
+                        &mut player_physical,
+                        player_transform.translation - transform.translation,
+                        &mut sound,
+                    );
+                    clashed = true;
+                }
             }
         }
+        if clashed {
+            last_player_slash.line = None;
+        } else {
+            last_player_slash.line = Some((line.start, line_end));
+            last_player_slash.window.reset();
+        }
+    }
+}
+
+fn clash(
+    mirror: &mut MirrorSamurai,
+    mirror_physical: &mut Physical,
+    player_physical: &mut Physical,
+    apart: Vec3,
+    sound: &mut EventWriter<Sound>,
+) {
+    let normal = apart.truncate().normalize_or_zero();
+    mirror.last_slash = None;
+    mirror_physical.impulse(-normal * CLASH_KNOCKBACK);
+    player_physical.impulse(normal * CLASH_KNOCKBACK);
+    // Both sides get their invulnerability so neither the slash nor the contact lands.
+    mirror_physical.hit_cooldown.reset();
+    player_physical.hit_cooldown.reset();
+    sound.send(Sound {
+        id: SoundId::Hit,
+        position: mirror_physical.collider.center.extend(0.0),
+        speed: 1.6,
+    });
+}
+
+fn mirror_sprite_states(
+    mirrors: Query<&MirrorSamurai>,
+    mut sprites: Query<(&Parent, &mut Animate), With<MirrorSprite>>,
+) {
+    for (parent, mut anim) in sprites.iter_mut() {
+        let Ok(mirror) = mirrors.get(parent.get()) else {
+            continue;
+        };
+        let (first, last) = match mirror.stance {
+            MirrorStance::Stalking => (0, 1),
+            MirrorStance::Telegraph => (3, 3),
+            MirrorStance::Pause => (2, 2),
+        };
+        if anim.first != first {
+            anim.first = first;
+            anim.last = last;
+            anim.current = first;
+            anim.speed = if first == last { 0.0 } else { 3.0 };
+            anim.timer.reset();
+            anim.timer.unpause();
+        }
     }
 }
 
-fn finish_him(
+fn draw_mirror_telegraphs(mut gizmos: Gizmos, mirrors: Query<(&MirrorSamurai, &Transform)>) {
+    for (mirror, transform) in mirrors.iter() {
+        if mirror.stance == MirrorStance::Telegraph {
+            let start = transform.translation.truncate();
+            gizmos.line_2d(
+                start,
+                start + *mirror.direction * SLASH_DISTANCE,
+                Color::srgba(4.0, 0.25, 0.25, mirror.telegraph.fraction()),
+            );
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct EnemyKilled {
+    pub position: Vec3,
+}
+
+pub fn finish_him(
     mut global: ResMut<GameGlobal>,
     mut commands: Commands,
     mut finish_events: EventReader<Finish>,
     mut sound: EventWriter<Sound>,
+    mut killed: EventWriter<EnemyKilled>,
     mut enemies: Query<(Entity, &Transform), With<EnemyHit>>,
 ) {
     for finisher in finish_events.read() {
         for (entity, transform) in enemies.iter_mut() {
             sound.send(Sound {
-                name: "kill".to_string(),
+                id: SoundId::Kill,
                 position: transform.translation,
                 speed: 1.0,
             });
             commands.entity(entity).despawn_recursive();
+            killed.send(EnemyKilled {
+                position: transform.translation,
+            });
             global.kills += 1;
         }
         return;
     }
 }
 
-fn destroy_enemies(mut commands: Commands, enemies: Query<Entity, With<Enemy>>) {
+pub fn destroy_enemies(mut commands: Commands, enemies: Query<Entity, With<Enemy>>) {
     for enemy in enemies.iter() {
         commands.entity(enemy).despawn_recursive();
     }
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum DarkPresenceMode {
+    /// An entity hunts the player through the outer zone and kills on contact.
+    Hunter,
+    /// The old fallback: staying outside the window until the timer runs out ends the run.
+    Timer,
+}
+
+#[derive(Resource)]
+pub struct DarkPresenceConfig {
+    pub mode: DarkPresenceMode,
+    pub base_speed: f32,
+    pub speed_ramp: f32,
+}
+
+impl Default for DarkPresenceConfig {
+    fn default() -> DarkPresenceConfig {
+        DarkPresenceConfig {
+            mode: DarkPresenceMode::Hunter,
+            base_speed: 60.0,
+            speed_ramp: 45.0,
+        }
+    }
+}
+
 #[derive(Component)]
-struct DarkPresence {
-    timer: Timer,
+pub struct DarkPresence {
+    pub timer: Timer,
+    cued: bool,
+}
+
+#[derive(Component)]
+struct DarkPresenceCountdown;
+
+#[derive(Component)]
+pub struct DarkHunter {
+    hunting: Stopwatch,
+    pub collider: BoundingCircle,
+    alpha: f32,
+    cued: bool,
 }
 
 #[derive(Component)]
 struct DarkPresenceAttack {
     timer: Timer,
+    /// Fades in over the whole timer instead of flashing, for reduced flashing.
+    fade: bool,
 }
 
+/// How long the attack flash holds before the run ends, in seconds.
+const ATTACK_FLASH: f32 = 0.5;
+
 #[derive(Component)]
 struct DarkPresenceSprite;
 
-fn setup_dark_presence(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    global: Res<GameGlobal>,
-) {
+fn setup_dark_presence(mut commands: Commands, assets: Res<GameAssets>, global: Res<GameGlobal>) {
     commands.spawn((
         DarkPresenceSprite,
         DarkPresence {
             timer: Timer::from_seconds(6.0, TimerMode::Once),
+            cued: false,
         },
         SpriteBundle {
             sprite: Sprite {
                 color: Color::srgba(4.0, 4.0, 4.0, 0.0),
                 ..default()
             },
-            texture: asset_server.load("embedded://DarkPresence.png"),
+            texture: assets.dark_presence.clone(),
             transform: Transform::from_scale(Vec3::splat(40.0 * global.camera_scale))
                 .with_translation(Vec3::new(0.0, 0.0, 30.0)),
             ..default()
@@ -886,26 +1438,46 @@ fn setup_dark_presence(
     commands.spawn((
         DarkPresenceSprite,
         DarkPresenceAttack {
-            timer: Timer::from_seconds(0.5, TimerMode::Once),
+            timer: Timer::from_seconds(ATTACK_FLASH, TimerMode::Once),
+            fade: false,
         },
         SpriteBundle {
             sprite: Sprite {
                 color: Color::srgba(4.0, 4.0, 4.0, 0.0),
                 ..default()
             },
-            texture: asset_server.load("embedded://DarkPresenceAttack.png"),
+            texture: assets.dark_presence_attack.clone(),
             transform: Transform::from_scale(Vec3::splat(40.0 * global.camera_scale))
                 .with_translation(Vec3::new(0.0, 0.0, 40.0)),
             ..default()
         },
     ));
+    commands.spawn((
+        DarkPresenceCountdown,
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 120.0,
+                    color: Color::srgba(2.5, 0.25, 0.25, 0.0),
+                    ..default()
+                },
+            )
+            .with_justify(JustifyText::Center),
+            transform: Transform::from_xyz(0.0, 0.0, 20.0),
+            ..default()
+        },
+    ));
 }
 
 fn update_dark_presence(
     time: Res<Time>,
     mut global: ResMut<GameGlobal>,
+    settings: Res<Settings>,
     player: Query<&Transform, With<Player>>,
-    mut game_state: ResMut<NextState<GameState>>,
+    mut died: EventWriter<PlayerDied>,
+    config: Res<DarkPresenceConfig>,
     mut presence_q: Query<(&mut DarkPresence, &mut Sprite)>,
 ) {
     if player.is_empty() {
@@ -917,12 +1489,22 @@ fn update_dark_presence(
     }
     let (mut presence, mut sprite) = presence_q.single_mut();
 
-    if f32::abs(position.translation.x) > global.inner_world_size.x / 2.0
-        || f32::abs(position.translation.y) > global.inner_world_size.y / 2.0
+    // The hunter stands in for both the timer and the screen-wide overlay, see
+    // `update_dark_hunter`.
+    if config.mode == DarkPresenceMode::Timer
+        && (f32::abs(position.translation.x) > global.inner_world_size.x / 2.0
+            || f32::abs(position.translation.y) > global.inner_world_size.y / 2.0)
     {
         presence.timer.tick(time.delta());
         if presence.timer.just_finished() {
-            game_state.set(GameState::DarkPresenceAttack);
+            died.send(PlayerDied {
+                cause: DeathCause::DarkPresence,
+            });
+        } else if settings.reduced_flashing {
+            // The flicker's average, without the flicker.
+            sprite
+                .color
+                .set_alpha(f32::max(presence.timer.fraction() * 0.0105 - 0.006, 0.0));
         } else {
             sprite.color.set_alpha(
                 f32::max(presence.timer.fraction() * 0.01 - 0.006, 0.0)
@@ -934,13 +1516,213 @@ fn update_dark_presence(
         }
     } else {
         presence.timer.reset();
+        presence.cued = false;
         sprite.color.set_alpha(0.0);
     }
 }
 
-fn dark_presence_attack(
+/// The countdown never shows more seconds than this.
+const COUNTDOWN_LONGEST: u32 = 6;
+
+/// Counts down over the player in numerals and swells the noise loop as the timer runs.
+fn dark_presence_warning(
+    time: Res<Time>,
+    global: Res<GameGlobal>,
+    config: Res<DarkPresenceConfig>,
+    localizer: Localizer,
+    mut sound: EventWriter<Sound>,
+    player: Query<&Transform, With<Player>>,
+    mut presence_q: Query<&mut DarkPresence>,
+    mut countdown_q: Query<
+        (&mut Text, &mut Transform),
+        (With<DarkPresenceCountdown>, Without<Player>),
+    >,
+    mut noise: Query<(&AudioSink, &mut Mixed), With<Noise>>,
+) {
+    if player.is_empty() || presence_q.is_empty() || countdown_q.is_empty() {
+        return;
+    }
+    let position = player.single().translation;
+    let mut presence = presence_q.single_mut();
+    let (mut text, mut transform) = countdown_q.single_mut();
+    let outside = f32::abs(position.x) > global.inner_world_size.x / 2.0
+        || f32::abs(position.y) > global.inner_world_size.y / 2.0;
+    let fraction = presence.timer.fraction();
+
+    transform.translation = position.truncate().extend(20.0) + Vec3::new(0.0, 80.0, 0.0);
+    if outside {
+        let glyph = match config.mode {
+            DarkPresenceMode::Timer => {
+                let remaining = presence.timer.remaining_secs().ceil() as u32;
+                localizer.numeral(remaining.clamp(1, COUNTDOWN_LONGEST))
+            }
+            DarkPresenceMode::Hunter => localizer.get("death"),
+        };
+        localizer.set(&mut text, glyph);
+        text.sections[0].style.color.set_alpha(0.2 + 0.8 * fraction);
+    } else {
+        text.sections[0].style.color.set_alpha(0.0);
+    }
+
+    if outside
+        && config.mode == DarkPresenceMode::Timer
+        && !presence.cued
+        && presence.timer.remaining_secs() < 1.0
+    {
+        presence.cued = true;
+        sound.send(Sound {
+            id: SoundId::Omen,
+            position,
+            speed: 1.0,
+        });
+    }
+
+    if let Ok((sink, mut mixed)) = noise.get_single_mut() {
+        let target = if outside { 0.25 + 0.75 * fraction } else { 0.0 };
+        let step = time.delta_seconds() * 2.0;
+        let volume = if mixed.volume < target {
+            f32::min(mixed.volume + step, target)
+        } else {
+            f32::max(mixed.volume - step, target)
+        };
+        mixed.volume = volume;
+        sink.set_speed(1.0 + 0.5 * fraction);
+        if outside {
+            sink.play();
+        } else if volume <= 0.0 {
+            sink.pause();
+        }
+    }
+}
+
+fn hide_dark_presence_warning(mut countdown_q: Query<&mut Text, With<DarkPresenceCountdown>>) {
+    for mut text in countdown_q.iter_mut() {
+        text.sections[0].style.color.set_alpha(0.0);
+    }
+}
+
+/// Picks a spot in the middle of the outer zone a quarter turn away from the player.
+fn dark_hunter_spawn_point(global: &mut GameGlobal, player: Vec2) -> Vec2 {
+    let side = if global.rand.gen::<bool>() { 1.0 } else { -1.0 };
+    let direction = Vec2::from_angle(side * PI / 2.0).rotate(player.normalize_or_zero());
+    let band = global.inner_world_size * 7.0 / 12.0;
+    let scale = f32::min(
+        band.x / f32::max(f32::abs(direction.x), f32::EPSILON),
+        band.y / f32::max(f32::abs(direction.y), f32::EPSILON),
+    );
+    direction * scale
+}
+
+fn update_dark_hunter(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut global: ResMut<GameGlobal>,
+    config: Res<DarkPresenceConfig>,
+    assets: Res<GameAssets>,
+    mut sound: EventWriter<Sound>,
+    player: Query<(&Transform, &Physical), With<Player>>,
+    mut hunters: Query<(Entity, &mut DarkHunter, &mut Transform, &mut Sprite), Without<Player>>,
+    mut died: EventWriter<PlayerDied>,
+) {
+    if player.is_empty() || config.mode != DarkPresenceMode::Hunter {
+        return;
+    }
+    let (player_transform, player_physical) = player.single();
+    let position = player_transform.translation.truncate();
+    let outside = f32::abs(position.x) > global.inner_world_size.x / 2.0
+        || f32::abs(position.y) > global.inner_world_size.y / 2.0;
+
+    if outside && hunters.is_empty() {
+        let spawn = dark_hunter_spawn_point(&mut global, position);
+        commands.spawn((
+            DarkHunter {
+                hunting: Stopwatch::new(),
+                collider: BoundingCircle::new(spawn, 40.0),
+                alpha: 0.0,
+                cued: false,
+            },
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::srgba(4.0, 4.0, 4.0, 0.0),
+                    ..default()
+                },
+                texture: assets.dark_presence.clone(),
+                transform: Transform::from_scale(Vec3::splat(3.0))
+                    .with_translation(spawn.extend(30.0)),
+                ..default()
+            },
+        ));
+        return;
+    }
+
+    for (entity, mut hunter, mut transform, mut sprite) in hunters.iter_mut() {
+        if outside {
+            hunter.hunting.tick(time.delta());
+            let speed = config.base_speed + config.speed_ramp * hunter.hunting.elapsed_secs();
+            let to_player = position - transform.translation.truncate();
+            let step = to_player.normalize_or_zero()
+                * f32::min(speed * time.delta_seconds(), to_player.length());
+            transform.translation += step.extend(0.0);
+            hunter.alpha = f32::min(hunter.alpha + time.delta_seconds(), 1.0);
+            // One second out from contact, the same cue the timer gives on its last second.
+            if !hunter.cued && to_player.length() < speed {
+                hunter.cued = true;
+                sound.send(Sound {
+                    id: SoundId::Omen,
+                    position: player_transform.translation,
+                    speed: 1.0,
+                });
+            }
+        } else {
+            hunter.alpha -= f32::min(time.delta_seconds() * 2.0, hunter.alpha);
+            if hunter.alpha <= 0.0 {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        }
+        hunter.collider.center = transform.translation.truncate();
+        sprite.color.set_alpha(hunter.alpha);
+
+        if outside && hunter.collider.intersects(&player_physical.collider) {
+            died.send(PlayerDied {
+                cause: DeathCause::DarkPresence,
+            });
+        }
+    }
+}
+
+fn destroy_dark_hunter(mut commands: Commands, hunters: Query<Entity, With<DarkHunter>>) {
+    for hunter in hunters.iter() {
+        commands.entity(hunter).despawn_recursive();
+    }
+}
+
+/// Sent to calm the Dark Presence back down, as if the player had just stepped back inside.
+#[derive(Event)]
+pub struct DarkPresenceReset;
+
+/// The timer starts over, and a hunter is sent away. If the player is still outside, a new one
+/// comes in from the far side of the field at its starting speed.
+fn reset_dark_presence(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    mut resets: EventReader<DarkPresenceReset>,
+    mut presence_q: Query<&mut DarkPresence>,
+    hunters: Query<Entity, With<DarkHunter>>,
+) {
+    for reset in resets.read() {
+        for mut presence in presence_q.iter_mut() {
+            presence.timer.reset();
+            presence.cued = false;
+        }
+        for hunter in hunters.iter() {
+            commands.entity(hunter).despawn_recursive();
+        }
+    }
+}
+
+fn dark_presence_attack(
+    settings: Res<Settings>,
+    mut sound: EventWriter<Sound>,
     mut sprite: Query<(&mut Sprite, &mut DarkPresenceAttack)>,
 ) {
     if sprite.is_empty() {
@@ -948,33 +1730,56 @@ fn dark_presence_attack(
         return;
     }
     let (mut sprite, mut attack) = sprite.single_mut();
-    sprite.color.set_alpha(1.0);
+    attack.fade = settings.reduced_flashing;
+    if attack.fade {
+        attack
+            .timer
+            .set_duration(Duration::from_secs_f32(REDUCED_ATTACK_FADE));
+    } else {
+        attack
+            .timer
+            .set_duration(Duration::from_secs_f32(ATTACK_FLASH));
+        sprite.color.set_alpha(1.0);
+    }
     attack.timer.reset();
 
-    commands.spawn((
-        SpatialBundle {
-            transform: Transform::from_xyz(0.0, 0.0, 0.0),
-            ..default()
-        },
-        AudioBundle {
-            source: asset_server.load("embedded://attack.mp3"),
-            settings: PlaybackSettings::DESPAWN.with_spatial(false),
-        },
-    ));
+    sound.send(Sound {
+        id: SoundId::Attack,
+        position: Vec3::ZERO,
+        speed: 1.0,
+    });
 }
 
+/// After the flash the run ends through `GameOver` like any other death, fading out from the
+/// attack the same way it fades out from the last hit.
 fn dark_presence_attack_timer(
     time: Res<Time>,
-    mut query: Query<&mut DarkPresenceAttack>,
+    mut query: Query<(&mut Sprite, &mut DarkPresenceAttack)>,
     mut game_state: ResMut<NextState<GameState>>,
 ) {
     if query.is_empty() {
         return;
     }
-    let mut attack = query.single_mut();
+    let (mut sprite, mut attack) = query.single_mut();
     attack.timer.tick(time.delta());
+    if attack.fade {
+        sprite.color.set_alpha(attack.timer.fraction());
+    }
     if attack.timer.finished() {
-        game_state.set(GameState::Menu);
+        game_state.set(GameState::GameOver);
+    }
+}
+
+/// Lets the attack sink away so the game over summary shows through.
+fn fade_dark_presence_attack(
+    time: Res<Time>,
+    mut sprites: Query<&mut Sprite, With<DarkPresenceAttack>>,
+) {
+    for mut sprite in sprites.iter_mut() {
+        let alpha = sprite.color.alpha();
+        sprite
+            .color
+            .set_alpha(f32::max(alpha - time.delta_seconds(), 0.0));
     }
 }
 
@@ -983,3 +1788,57 @@ fn dark_presence_remove(mut sprites: Query<&mut Sprite, With<DarkPresenceSprite>
         sprite.color.set_alpha(0.0);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::bounding::Aabb2d;
+
+    fn slash() -> SlashCapsule {
+        SlashCapsule::new(Vec2::ZERO, Dir2::X, SLASH_DISTANCE, SLASH_WIDTH)
+    }
+
+    #[test]
+    fn capsule_catches_circles_between_the_old_rays() {
+        let capsule = slash();
+        let small = |x: f32, y: f32| BoundingCircle::new(Vec2::new(x, y), 2.0);
+        assert!(capsule.intersects_circle(&small(SLASH_DISTANCE / 2.0, 0.0)));
+        assert!(capsule.intersects_circle(&small(0.0, 0.0)));
+        assert!(capsule.intersects_circle(&small(SLASH_DISTANCE + SLASH_WIDTH, 0.0)));
+        assert!(capsule.intersects_circle(&small(SLASH_DISTANCE / 2.0, SLASH_WIDTH + 1.5)));
+        assert!(!capsule.intersects_circle(&small(SLASH_DISTANCE / 2.0, SLASH_WIDTH + 2.5)));
+        assert!(!capsule.intersects_circle(&small(-SLASH_WIDTH - 2.5, 0.0)));
+    }
+
+    #[test]
+    fn tip_reaches_past_the_width() {
+        let capsule = slash();
+        let tip = SLASH_WIDTH + SLASH_TIP_REACH;
+        assert_eq!(capsule.tip, 15.0);
+        assert_eq!(capsule.reach(), tip);
+        let small = |x: f32, y: f32| BoundingCircle::new(Vec2::new(x, y), 2.0);
+        assert!(capsule.intersects_circle(&small(SLASH_DISTANCE + tip + 1.5, 0.0)));
+        assert!(!capsule.intersects_circle(&small(SLASH_DISTANCE + tip + 2.5, 0.0)));
+        assert!(capsule.intersects_circle(&small(SLASH_DISTANCE, tip + 1.5)));
+        assert!(!capsule.intersects_circle(&small(SLASH_DISTANCE / 2.0, tip)));
+        // Walls only meet the swept body, not the tip.
+        let wall = Shape::Circle {
+            center: Vec2::new(SLASH_DISTANCE + tip, 0.0),
+            radius: 2.0,
+        };
+        assert!(!capsule.intersects(&wall));
+    }
+
+    #[test]
+    fn capsule_catches_boxes_between_the_old_rays() {
+        let capsule = slash();
+        let small = |x: f32, y: f32| Shape::Aabb(Aabb2d::new(Vec2::new(x, y), Vec2::splat(2.0)));
+        assert!(capsule.intersects(&small(SLASH_DISTANCE / 2.0, 0.0)));
+        assert!(capsule.intersects(&small(0.0, 0.0)));
+        assert!(capsule.intersects(&small(SLASH_DISTANCE / 2.0, -SLASH_WIDTH - 1.5)));
+        assert!(!capsule.intersects(&small(SLASH_DISTANCE / 2.0, -SLASH_WIDTH - 2.5)));
+        // Off the end the corner is further away than the face.
+        let corner = SLASH_DISTANCE + 2.0 + SLASH_WIDTH * 0.8;
+        assert!(!capsule.intersects(&small(corner, SLASH_WIDTH * 0.8 + 2.0)));
+    }
+}
@@ -0,0 +1,252 @@
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use crate::bushido::assets::GameAssets;
+use crate::bushido::settings::Settings;
+use crate::bushido::GameState;
+use ab_glyph::Font as _;
+use bevy::asset::io::Reader;
+use bevy::asset::AssetLoader;
+use bevy::asset::AsyncReadExt;
+use bevy::asset::LoadContext;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use std::io::Error;
+use std::io::ErrorKind;
+
+pub struct LocalePlugin;
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Locale>()
+            .register_asset_loader(LocaleLoader)
+            .add_systems(OnExit(GameState::Loading), check_glyphs)
+            .add_systems(
+                Update,
+                localize_texts.run_if(not(in_state(GameState::Loading))),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Language {
+    #[default]
+    English,
+    Japanese,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Japanese];
+
+    /// The file under `locale/` holding its strings.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Japanese => "ja",
+        }
+    }
+
+    pub fn next(&self) -> Language {
+        let index = Language::ALL.iter().position(|language| language == self);
+        Language::ALL[(index.unwrap_or(0) + 1) % Language::ALL.len()]
+    }
+}
+
+/// Every player-facing string in one language, by key.
+#[derive(Asset, TypePath)]
+pub struct Locale {
+    pub strings: HashMap<String, String>,
+}
+
+#[derive(Default)]
+struct LocaleLoader;
+
+impl AssetLoader for LocaleLoader {
+    type Asset = Locale;
+    type Settings = ();
+    type Error = Error;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Locale, Error> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).await?;
+        parse_locale(&text)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["lang"]
+    }
+}
+
+/// One `key = value` per line. Lines starting with `#` are comments and `\n` in a value breaks
+/// the line.
+fn parse_locale(text: &str) -> Result<Locale, Error> {
+    let mut strings = HashMap::default();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("locale line {}: {}", number + 1, line),
+            ));
+        };
+        strings.insert(key.trim().to_string(), value.trim().replace("\\n", "\n"));
+    }
+    Ok(Locale { strings })
+}
+
+/// Looks up text in the language picked in `Settings`, falling back to English and then to the
+/// key itself, and picks a font that can draw it.
+#[derive(SystemParam)]
+pub struct Localizer<'w> {
+    settings: Res<'w, Settings>,
+    assets: Res<'w, GameAssets>,
+    locales: Res<'w, Assets<Locale>>,
+    fonts: Res<'w, Assets<Font>>,
+}
+
+impl<'w> Localizer<'w> {
+    pub fn get(&self, key: &str) -> String {
+        self.lookup(self.settings.language, key)
+            .or_else(|| self.lookup(Language::English, key))
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// Fills every `{name}` in the string with its value.
+    pub fn format(&self, key: &str, args: &[(&str, String)]) -> String {
+        let mut text = self.get(key);
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        text
+    }
+
+    /// Writes 1 to 99 with the language's numerals, see `numeral`.
+    pub fn numeral(&self, n: u32) -> String {
+        numeral(n, &self.get("numeral-digits"), &self.get("numeral-ten"))
+    }
+
+    /// Saruji, unless it's missing a glyph the text needs and a fallback font has all of them.
+    /// When none of them can draw the whole text it stays with saruji rather than trading one
+    /// set of boxes for another.
+    pub fn font(&self, text: &str) -> Handle<Font> {
+        std::iter::once(&self.assets.font)
+            .chain(&self.assets.fallback_fonts)
+            .find(|handle| {
+                self.fonts
+                    .get(*handle)
+                    .is_some_and(|font| missing_glyphs(font, text).is_empty())
+            })
+            .unwrap_or(&self.assets.font)
+            .clone()
+    }
+
+    /// Sets the first section of `text`, and its font, only when the value actually changes.
+    pub fn set(&self, text: &mut Mut<Text>, value: String) {
+        if text.sections[0].value == value {
+            return;
+        }
+        text.sections[0].style.font = self.font(&value);
+        text.sections[0].value = value;
+    }
+
+    fn lookup(&self, language: Language, key: &str) -> Option<String> {
+        let locale = self.locales.get(&self.assets.locales[language as usize])?;
+        locale.strings.get(key).cloned()
+    }
+}
+
+/// Writes 1 to 99 from ten `digits` (the first one for zero) and a `ten` that goes between the
+/// tens and the ones, the way kanji numerals are. Past that it falls back to arabic numerals.
+pub fn numeral(n: u32, digits: &str, ten: &str) -> String {
+    let digits = digits.chars().collect::<Vec<char>>();
+    if digits.len() != 10 {
+        return n.to_string();
+    }
+    match n {
+        0..=9 => digits[n as usize].to_string(),
+        10..=99 => {
+            let mut text = String::new();
+            if n / 10 > 1 {
+                text.push(digits[(n / 10) as usize]);
+            }
+            text.push_str(ten);
+            if n % 10 > 0 {
+                text.push(digits[(n % 10) as usize]);
+            }
+            text
+        }
+        _ => n.to_string(),
+    }
+}
+
+fn missing_glyphs(font: &Font, text: &str) -> Vec<char> {
+    text.chars()
+        .filter(|c| !c.is_whitespace() && font.font.glyph_id(*c).0 == 0)
+        .collect()
+}
+
+/// Text that only ever shows one string, kept in the current language.
+#[derive(Component)]
+pub struct Localized {
+    pub key: &'static str,
+}
+
+fn localize_texts(localizer: Localizer, mut texts: Query<(&Localized, &mut Text)>) {
+    for (localized, mut text) in texts.iter_mut() {
+        localizer.set(&mut text, localizer.get(localized.key));
+    }
+}
+
+/// Logs every character each language uses that saruji can't draw, so they show up before a
+/// player meets the fallback fonts, and louder for the ones no fallback can draw either.
+fn check_glyphs(assets: Res<GameAssets>, locales: Res<Assets<Locale>>, fonts: Res<Assets<Font>>) {
+    let Some(font) = fonts.get(&assets.font) else {
+        return;
+    };
+    let fallbacks = assets
+        .fallback_fonts
+        .iter()
+        .filter_map(|handle| fonts.get(handle))
+        .collect::<Vec<&Font>>();
+    for language in Language::ALL {
+        let Some(locale) = locales.get(&assets.locales[language as usize]) else {
+            warn!("No strings for {:?}", language);
+            continue;
+        };
+        let mut missing = locale
+            .strings
+            .values()
+            .flat_map(|value| missing_glyphs(font, value))
+            .collect::<Vec<char>>();
+        missing.sort();
+        missing.dedup();
+        if missing.is_empty() {
+            continue;
+        }
+        let (drawable, undrawable): (Vec<char>, Vec<char>) = missing.into_iter().partition(|c| {
+            fallbacks
+                .iter()
+                .any(|fallback| missing_glyphs(fallback, &c.to_string()).is_empty())
+        });
+        if !drawable.is_empty() {
+            warn!(
+                "Saruji is missing {:?} for {:?}, those strings use a fallback font",
+                drawable.iter().collect::<String>(),
+                language
+            );
+        }
+        if !undrawable.is_empty() {
+            error!(
+                "No font has {:?} for {:?}, those strings will show boxes",
+                undrawable.iter().collect::<String>(),
+                language
+            );
+        }
+    }
+}
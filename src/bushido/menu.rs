@@ -1,15 +1,21 @@
 #![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
-use crate::bushido::player::PlayerHit;
+use crate::bushido::accessibility::REDUCED_HITCOUNT_ALPHA;
+use crate::bushido::accessibility::REDUCED_HITCOUNT_RISE;
+use crate::bushido::accessibility::REDUCED_HITCOUNT_STRETCH;
+use crate::bushido::assets::GameAssets;
+use crate::bushido::locale::Localized;
+use crate::bushido::locale::Localizer;
+use crate::bushido::DeathCause;
 use crate::bushido::GameState;
+use crate::bushido::Stats;
 use crate::GameGlobal;
 use bevy::prelude::*;
-use bevy::utils::Duration;
 use bevy_hanabi::position;
 
 pub struct MenuPlugin;
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, (setup_menu, setup_hitcounts, setup_scoreboard))
+        app.add_systems(Startup, (setup_menu, setup_scoreboard))
             .add_systems(Update, (update_scoreboard, fade_hitcounts))
             .add_systems(OnEnter(GameState::Menu), show_menu)
             .add_systems(OnEnter(GameState::Play), hide_menu);
@@ -19,20 +25,22 @@ impl Plugin for MenuPlugin {
 #[derive(Component)]
 struct Menu;
 
-fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup_menu(mut commands: Commands, assets: Res<GameAssets>) {
     commands.spawn((
         Menu,
+        Localized { key: "title" },
         Text2dBundle {
             text: Text::from_section(
-                "Bushido Blazer",
+                "",
                 TextStyle {
-                    font: asset_server.load("embedded://saruji.ttf"),
+                    font: assets.font.clone(),
                     font_size: 225.0,
                     color: Color::srgb(2.5, 0.25, 0.25),
                     ..default()
                 },
             )
             .with_justify(JustifyText::Center),
+            visibility: Visibility::Hidden,
             transform: Transform::from_xyz(0.0, 140.0, 10.0),
             ..default()
         },
@@ -42,94 +50,48 @@ fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
 #[derive(Component)]
 pub struct Hitcount {
     pub timer: Timer,
+    /// The brightest it gets, as alpha.
+    pub peak: f32,
+    /// The share of the timer spent fading in first, 0 to appear at once.
+    pub rise: f32,
 }
 
-#[derive(Component)]
-pub struct Ichi;
-
-#[derive(Component)]
-pub struct Ni;
-
-#[derive(Component)]
-pub struct San;
-
-#[derive(Component)]
-pub struct Shi;
-
-fn setup_hitcounts(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.spawn((
-        Hitcount {
-            timer: Timer::new(Duration::from_secs_f64(1.0), TimerMode::Once),
-        },
-        Ichi,
-        Text2dBundle {
-            text: Text::from_section(
-                "一",
-                TextStyle {
-                    font: asset_server.load("embedded://saruji.ttf"),
-                    font_size: 900.0,
-                    color: Color::srgba(2.5, 0.25, 0.25, 0.0),
-                    ..default()
-                },
-            )
-            .with_justify(JustifyText::Center),
-            transform: Transform::from_xyz(0.0, 0.0, 10.0),
-            visibility: Visibility::Hidden,
-            ..default()
-        },
-    ));
-    commands.spawn((
-        Hitcount {
-            timer: Timer::new(Duration::from_secs_f64(1.0), TimerMode::Once),
-        },
-        Ni,
-        Text2dBundle {
-            text: Text::from_section(
-                "二",
-                TextStyle {
-                    font: asset_server.load("embedded://saruji.ttf"),
-                    font_size: 900.0,
-                    color: Color::srgba(2.5, 0.25, 0.25, 0.0),
-                    ..default()
-                },
-            )
-            .with_justify(JustifyText::Center),
-            transform: Transform::from_xyz(0.0, 0.0, 10.0),
-            visibility: Visibility::Hidden,
-            ..default()
-        },
-    ));
-    commands.spawn((
-        Hitcount {
-            timer: Timer::new(Duration::from_secs_f64(1.0), TimerMode::Once),
-        },
-        San,
-        Text2dBundle {
-            text: Text::from_section(
-                "三",
-                TextStyle {
-                    font: asset_server.load("embedded://saruji.ttf"),
-                    font_size: 900.0,
-                    color: Color::srgba(2.5, 0.25, 0.25, 0.0),
-                    ..default()
-                },
-            )
-            .with_justify(JustifyText::Center),
-            transform: Transform::from_xyz(0.0, 0.0, 10.0),
-            visibility: Visibility::Hidden,
-            ..default()
-        },
-    ));
+/// Flashes the number of hits taken, or 死 for the last one. With reduced flashing it's dimmer and
+/// rises and falls slowly instead.
+pub fn spawn_hitcount(
+    commands: &mut Commands,
+    localizer: &Localizer,
+    hits: u32,
+    dead: bool,
+    reduced_flashing: bool,
+) {
+    let (glyph, mut seconds) = if dead {
+        (localizer.get("death"), 4.0)
+    } else {
+        (localizer.numeral(hits), 1.0)
+    };
+    let hitcount;
+    if reduced_flashing {
+        seconds *= REDUCED_HITCOUNT_STRETCH;
+        hitcount = Hitcount {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+            peak: REDUCED_HITCOUNT_ALPHA,
+            rise: REDUCED_HITCOUNT_RISE,
+        };
+    } else {
+        hitcount = Hitcount {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+            peak: 0.5,
+            rise: 0.0,
+        };
+    }
     commands.spawn((
-        Hitcount {
-            timer: Timer::new(Duration::from_secs_f64(4.0), TimerMode::Once),
-        },
-        Shi,
+        hitcount,
         Text2dBundle {
             text: Text::from_section(
-                "死",
+                glyph.clone(),
                 TextStyle {
-                    font: asset_server.load("embedded://saruji.ttf"),
+                    font: localizer.font(&glyph),
                     font_size: 900.0,
                     color: Color::srgba(2.5, 0.25, 0.25, 0.0),
                     ..default()
@@ -137,25 +99,32 @@ fn setup_hitcounts(mut commands: Commands, asset_server: Res<AssetServer>) {
             )
             .with_justify(JustifyText::Center),
             transform: Transform::from_xyz(0.0, 0.0, 10.0),
-            visibility: Visibility::Hidden,
             ..default()
         },
     ));
 }
 
 fn fade_hitcounts(
+    mut commands: Commands,
     time: Res<Time>,
-    mut hitcounts: Query<(&mut Hitcount, &mut Text, &mut Visibility)>,
+    mut hitcounts: Query<(Entity, &mut Hitcount, &mut Text)>,
 ) {
-    for (mut hitcount, mut text, mut visible) in hitcounts.iter_mut() {
+    for (entity, mut hitcount, mut text) in hitcounts.iter_mut() {
         hitcount.timer.tick(time.delta());
         if hitcount.timer.finished() {
-            *visible = Visibility::Visible;
+            commands.entity(entity).despawn_recursive();
+            continue;
         }
-        text.sections[0].style.color.set_alpha(
-            0.5 * (1.0
-                - hitcount.timer.elapsed().as_secs_f32() / hitcount.timer.duration().as_secs_f32()),
-        );
+        let progress = hitcount.timer.fraction();
+        let level = if progress < hitcount.rise {
+            progress / hitcount.rise
+        } else {
+            (1.0 - progress) / (1.0 - hitcount.rise)
+        };
+        text.sections[0]
+            .style
+            .color
+            .set_alpha(hitcount.peak * level);
     }
 }
 
@@ -176,14 +145,14 @@ fn hide_menu(mut menu_q: Query<(&Menu, &mut Visibility)>) {
 #[derive(Component)]
 struct Scoreboard;
 
-fn setup_scoreboard(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup_scoreboard(mut commands: Commands, assets: Res<GameAssets>) {
     commands.spawn((
         Scoreboard,
         Text2dBundle {
             text: Text::from_section(
-                "Kills: 0",
+                "",
                 TextStyle {
-                    font: asset_server.load("embedded://saruji.ttf"),
+                    font: assets.font.clone(),
                     font_size: 35.0,
                     color: Color::srgb(2.5, 0.25, 0.25),
                     ..default()
@@ -201,12 +170,26 @@ fn update_scoreboard(
     mut query: Query<(&Scoreboard, &mut Text, &mut Visibility)>,
     state: Res<State<GameState>>,
     global: Res<GameGlobal>,
+    stats: Res<Stats>,
+    localizer: Localizer,
 ) {
     if !query.is_empty() {
         let (score, mut text, mut vis) = query.single_mut();
         if *state.get() == GameState::Play || *state.get() == GameState::GameOver {
             *vis = Visibility::Visible;
-            text.sections[0].value = format!("Kills: {}", global.kills);
+            let mut kills = localizer.format("kills", &[("kills", global.kills.to_string())]);
+            if *state.get() == GameState::GameOver {
+                let deaths = |cause| stats.deaths.get(&cause).copied().unwrap_or(0).to_string();
+                kills.push('\n');
+                kills.push_str(&localizer.format(
+                    "deaths",
+                    &[
+                        ("wounds", deaths(DeathCause::Wounds)),
+                        ("presence", deaths(DeathCause::DarkPresence)),
+                    ],
+                ));
+            }
+            localizer.set(&mut text, kills);
         } else {
             *vis = Visibility::Hidden;
         }
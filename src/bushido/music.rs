@@ -0,0 +1,423 @@
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use crate::bushido::assets::GameAssets;
+use crate::bushido::enemy::DarkHunter;
+use crate::bushido::enemy::DarkPresence;
+use crate::bushido::enemy::Enemy;
+use crate::bushido::enemy::SpawnWaves;
+use crate::bushido::player::Player;
+use crate::bushido::player::PlayerHealth;
+use crate::bushido::sound::Bus;
+use crate::bushido::sound::Mixed;
+use crate::bushido::GameState;
+use crate::GameGlobal;
+use bevy::asset::io::Reader;
+use bevy::asset::AssetLoader;
+use bevy::asset::AsyncReadExt;
+use bevy::asset::LoadContext;
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use std::io::Error;
+use std::io::ErrorKind;
+
+/// Waves before this one only get the calm percussion.
+pub const EARLY_WAVES: i32 = 3;
+/// How many enemies on the field it takes for the crowd layer to come fully in.
+pub const CROWD_FULL: f32 = 20.0;
+/// How close the hunter has to get, in units, before its layer starts to come in.
+pub const PRESENCE_RANGE: f32 = 600.0;
+
+pub struct MusicPlugin;
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<MusicTrack>()
+            .register_asset_loader(MusicTrackLoader)
+            .insert_resource(Music::default())
+            .init_resource::<Mood>()
+            .add_systems(OnExit(GameState::Loading), start_music)
+            .add_systems(Update, (read_mood, mix_music.after(read_mood)));
+    }
+}
+
+/// What a stem follows in the game. Each one fades towards its own level between 0 and 1.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Layer {
+    Menu,
+    /// The sparse groove under the first few waves.
+    Calm,
+    /// The full groove once the early waves are over.
+    Drive,
+    /// Swells with the number of enemies on the field.
+    Crowd,
+    /// Swells with every hit the player takes.
+    Wounded,
+    /// In while the window is expanded.
+    Expanse,
+    /// Swells as the Dark Presence closes in.
+    Presence,
+}
+
+impl Layer {
+    fn parse(word: &str) -> Option<Layer> {
+        match word {
+            "menu" => Some(Layer::Menu),
+            "calm" => Some(Layer::Calm),
+            "drive" => Some(Layer::Drive),
+            "crowd" => Some(Layer::Crowd),
+            "wounded" => Some(Layer::Wounded),
+            "expanse" => Some(Layer::Expanse),
+            "presence" => Some(Layer::Presence),
+            _ => None,
+        }
+    }
+
+    /// The level this layer should sit at given the state of the game.
+    pub fn target(&self, mood: &Mood) -> f32 {
+        if mood.menu {
+            return on(*self == Layer::Menu);
+        }
+        match self {
+            Layer::Menu => 0.0,
+            Layer::Calm => on(mood.wave < EARLY_WAVES),
+            Layer::Drive => on(mood.wave >= EARLY_WAVES),
+            Layer::Crowd => f32::min(mood.enemies as f32 / CROWD_FULL, 1.0),
+            Layer::Wounded => mood.wounds,
+            Layer::Expanse => on(mood.expanded),
+            Layer::Presence => mood.presence,
+        }
+    }
+}
+
+fn on(value: bool) -> f32 {
+    if value {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// A piece of music split into stems that all loop together at the same tempo.
+#[derive(Asset, TypePath)]
+pub struct MusicTrack {
+    pub bpm: f32,
+    pub beats_per_bar: u32,
+    #[dependency]
+    pub stems: Vec<Handle<AudioSource>>,
+    pub layers: Vec<(Layer, f32)>,
+}
+
+impl MusicTrack {
+    pub fn beat_length(&self) -> f32 {
+        60.0 / self.bpm
+    }
+
+    pub fn bar_length(&self) -> f32 {
+        self.beat_length() * self.beats_per_bar as f32
+    }
+}
+
+#[derive(Default)]
+struct MusicTrackLoader;
+
+impl AssetLoader for MusicTrackLoader {
+    type Asset = MusicTrack;
+    type Settings = ();
+    type Error = Error;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<MusicTrack, Error> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).await?;
+        parse_track(&text, |file| {
+            let path = load_context
+                .asset_path()
+                .resolve_embed(file)
+                .map_err(|error| error.to_string())?;
+            Ok(load_context.load(path))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["music"]
+    }
+}
+
+/// A `tempo` line, then one `stem` line per file. `load` turns a stem's file name, relative to the
+/// track file, into its handle.
+fn parse_track(
+    text: &str,
+    mut load: impl FnMut(&str) -> Result<Handle<AudioSource>, String>,
+) -> Result<MusicTrack, Error> {
+    let mut track = MusicTrack {
+        bpm: 120.0,
+        beats_per_bar: 4,
+        stems: Vec::new(),
+        layers: Vec::new(),
+    };
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        match words.as_slice() {
+            ["tempo", bpm, beats_per_bar] => {
+                track.bpm = bpm.parse().map_err(|_| invalid(number, line))?;
+                track.beats_per_bar = beats_per_bar.parse().map_err(|_| invalid(number, line))?;
+            }
+            ["stem", file, layer, gain] => {
+                let layer = Layer::parse(layer).ok_or_else(|| invalid(number, line))?;
+                let gain = gain.parse().map_err(|_| invalid(number, line))?;
+                let stem = load(file).map_err(|error| invalid(number, &error))?;
+                track.stems.push(stem);
+                track.layers.push((layer, gain));
+            }
+            _ => return Err(invalid(number, line)),
+        }
+    }
+    if track.bpm <= 0.0 || track.beats_per_bar == 0 {
+        return Err(invalid(0, "tempo has to be positive"));
+    }
+    Ok(track)
+}
+
+fn invalid(number: usize, message: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("music line {}: {}", number + 1, message),
+    )
+}
+
+/// Keeps the stems on the beat. Their volume is on the music bus of the `Mixer`.
+#[derive(Resource)]
+pub struct Music {
+    /// When the stems actually started playing, in seconds since startup. `None` until every one
+    /// of them has a sink, which is only a frame or so after they're spawned.
+    started: Option<f32>,
+    /// The last beat the layer levels were latched on.
+    beat: i64,
+}
+
+impl Default for Music {
+    fn default() -> Music {
+        Music {
+            started: None,
+            beat: -1,
+        }
+    }
+}
+
+/// The live game state the layers follow, gathered every frame.
+#[derive(Resource, Default)]
+pub struct Mood {
+    pub menu: bool,
+    pub wave: i32,
+    pub enemies: usize,
+    /// The share of the player's health that's gone, between 0 and 1.
+    pub wounds: f32,
+    pub expanded: bool,
+    /// How close the Dark Presence is to taking the player, between 0 and 1.
+    pub presence: f32,
+}
+
+#[derive(Component)]
+struct Stem {
+    layer: Layer,
+    gain: f32,
+    level: f32,
+    target: f32,
+}
+
+/// Starts every stem at once, silent, so they stay in step however they get mixed later.
+fn start_music(
+    mut commands: Commands,
+    mut music: ResMut<Music>,
+    assets: Res<GameAssets>,
+    tracks: Res<Assets<MusicTrack>>,
+) {
+    let Some(track) = tracks.get(&assets.music) else {
+        warn!("No music to play");
+        return;
+    };
+    for (audio, (layer, gain)) in track.stems.iter().zip(track.layers.iter()) {
+        commands.spawn((
+            Stem {
+                layer: *layer,
+                gain: *gain,
+                level: 0.0,
+                target: 0.0,
+            },
+            Mixed {
+                bus: Bus::Music,
+                volume: 0.0,
+                ducks: false,
+            },
+            AudioBundle {
+                source: audio.clone(),
+                settings: PlaybackSettings::LOOP.with_volume(Volume::new(0.0)),
+            },
+        ));
+    }
+    music.started = None;
+    music.beat = -1;
+}
+
+fn read_mood(
+    global: Res<GameGlobal>,
+    state: Res<State<GameState>>,
+    waves: Res<SpawnWaves>,
+    enemies: Query<(), With<Enemy>>,
+    player: Query<(&Transform, &PlayerHealth), With<Player>>,
+    presence: Query<&DarkPresence>,
+    hunters: Query<&DarkHunter>,
+    mut mood: ResMut<Mood>,
+) {
+    mood.menu = matches!(
+        state.get(),
+        GameState::Loading | GameState::Prompt | GameState::Menu
+    );
+    mood.wave = waves.current;
+    mood.enemies = enemies.iter().count();
+    mood.expanded = global.expanded;
+    mood.wounds = 0.0;
+    mood.presence = 0.0;
+    let Ok((transform, health)) = player.get_single() else {
+        return;
+    };
+    mood.wounds = health.hits_taken() as f32 / health.max as f32;
+    for presence in presence.iter() {
+        mood.presence = f32::max(mood.presence, presence.timer.fraction());
+    }
+    for hunter in hunters.iter() {
+        let distance = hunter
+            .collider
+            .center
+            .distance(transform.translation.truncate());
+        mood.presence = f32::max(mood.presence, 1.0 - distance / PRESENCE_RANGE);
+    }
+    mood.presence = mood.presence.clamp(0.0, 1.0);
+}
+
+/// Layers only pick up a new level on the beat, then take a bar to fade over to it. The beat is
+/// counted from when the sinks started, not from when the stems were spawned, since decoding can
+/// hold them back a few frames.
+fn mix_music(
+    time: Res<Time>,
+    mood: Res<Mood>,
+    mut music: ResMut<Music>,
+    assets: Res<GameAssets>,
+    tracks: Res<Assets<MusicTrack>>,
+    sinks: Query<Has<AudioSink>, With<Stem>>,
+    mut stems: Query<(&mut Stem, &mut Mixed)>,
+) {
+    let Some(track) = tracks.get(&assets.music) else {
+        return;
+    };
+    let started = match music.started {
+        Some(started) => started,
+        None if !sinks.is_empty() && sinks.iter().all(|has_sink| has_sink) => {
+            music.started = Some(time.elapsed_seconds());
+            time.elapsed_seconds()
+        }
+        None => return,
+    };
+    let playing = time.elapsed_seconds() - started;
+    let beat = (playing / track.beat_length()).floor() as i64;
+    let on_beat = beat != music.beat;
+    music.beat = beat;
+    let step = time.delta_seconds() / track.bar_length();
+
+    for (mut stem, mut mixed) in stems.iter_mut() {
+        if on_beat {
+            stem.target = stem.layer.target(&mood);
+        }
+        let change = (stem.target - stem.level).clamp(-step, step);
+        stem.level += change;
+        mixed.volume = stem.level * stem.gain;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stems(text: &str) -> Result<MusicTrack, Error> {
+        parse_track(text, |_| Ok(Handle::default()))
+    }
+
+    #[test]
+    fn parses_tempo_stems_and_comments() {
+        let track = stems(
+            "# a comment\n\ntempo 120 3\n\
+             stem calm.ogg calm 0.5 # trailing\n\
+             stem a.ogg presence 1\n",
+        )
+        .unwrap();
+        assert_eq!(track.bpm, 120.0);
+        assert_eq!(track.beats_per_bar, 3);
+        assert_eq!(track.stems.len(), 2);
+        assert_eq!(
+            track.layers,
+            vec![(Layer::Calm, 0.5), (Layer::Presence, 1.0)]
+        );
+        assert_eq!(track.beat_length(), 0.5);
+        assert_eq!(track.bar_length(), 1.5);
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert!(stems("tempo fast 4").is_err());
+        assert!(stems("tempo 0 4").is_err());
+        assert!(stems("tempo 92 0").is_err());
+        assert!(stems("stem a.ogg loud 1").is_err());
+        assert!(stems("stem a.ogg calm").is_err());
+        assert!(stems("stem a.ogg calm much").is_err());
+        assert!(stems("drums a.ogg").is_err());
+        assert!(parse_track("stem a.ogg calm 1", |_| Err("no".to_string())).is_err());
+    }
+
+    #[test]
+    fn menu_mutes_everything_else() {
+        let mood = Mood {
+            menu: true,
+            wave: 10,
+            enemies: 40,
+            wounds: 1.0,
+            expanded: true,
+            presence: 1.0,
+        };
+        assert_eq!(Layer::Menu.target(&mood), 1.0);
+        for layer in [Layer::Calm, Layer::Drive, Layer::Crowd, Layer::Presence] {
+            assert_eq!(layer.target(&mood), 0.0);
+        }
+    }
+
+    #[test]
+    fn layers_follow_the_fight() {
+        let mut mood = Mood {
+            wave: EARLY_WAVES - 1,
+            enemies: (CROWD_FULL / 2.0) as usize,
+            wounds: 0.25,
+            presence: 0.75,
+            ..default()
+        };
+        assert_eq!(Layer::Menu.target(&mood), 0.0);
+        assert_eq!(Layer::Calm.target(&mood), 1.0);
+        assert_eq!(Layer::Drive.target(&mood), 0.0);
+        assert_eq!(Layer::Crowd.target(&mood), 0.5);
+        assert_eq!(Layer::Wounded.target(&mood), 0.25);
+        assert_eq!(Layer::Expanse.target(&mood), 0.0);
+        assert_eq!(Layer::Presence.target(&mood), 0.75);
+
+        mood.wave = EARLY_WAVES;
+        mood.enemies = CROWD_FULL as usize * 3;
+        mood.expanded = true;
+        assert_eq!(Layer::Calm.target(&mood), 0.0);
+        assert_eq!(Layer::Drive.target(&mood), 1.0);
+        assert_eq!(Layer::Crowd.target(&mood), 1.0);
+        assert_eq!(Layer::Expanse.target(&mood), 1.0);
+    }
+}
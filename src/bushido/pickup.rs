@@ -0,0 +1,331 @@
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use crate::bushido::enemy::DarkPresenceReset;
+use crate::bushido::enemy::EnemyKilled;
+use crate::bushido::locale::Localizer;
+use crate::bushido::player::Player;
+use crate::bushido::player::PlayerHealth;
+use crate::bushido::player::PLAYER_HIT_COOLDOWN;
+use crate::bushido::player::SLASH_COOLDOWN;
+use crate::bushido::player::SLASH_DISTANCE;
+use crate::bushido::settings::Settings;
+use crate::bushido::sound::SoundId;
+use crate::bushido::GameState;
+use crate::bushido::Physical;
+use crate::bushido::PlayPhase;
+use crate::bushido::Sound;
+use crate::GameGlobal;
+use bevy::math::bounding::BoundingCircle;
+use bevy::math::bounding::IntersectsVolume;
+use bevy::prelude::*;
+use rand::Rng;
+
+pub const DROP_CHANCE: f32 = 0.12;
+pub const PICKUP_LIFETIME: f32 = 12.0;
+
+pub struct PickupPlugin;
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_power_up_hud)
+            .add_systems(
+                Update,
+                (
+                    drop_pickups,
+                    collect_pickups,
+                    expire_pickups,
+                    tick_power_ups,
+                )
+                    .run_if(in_state(PlayPhase::Fighting)),
+            )
+            .add_systems(Update, update_power_up_hud)
+            .add_systems(OnExit(GameState::GameOver), destroy_pickups);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PickupKind {
+    Heal,
+    LongSlash,
+    QuickDraw,
+    IronWill,
+    Sanctuary,
+}
+
+impl PickupKind {
+    fn roll(roll: f32) -> PickupKind {
+        if roll < 0.25 {
+            PickupKind::Heal
+        } else if roll < 0.45 {
+            PickupKind::LongSlash
+        } else if roll < 0.65 {
+            PickupKind::QuickDraw
+        } else if roll < 0.85 {
+            PickupKind::IronWill
+        } else {
+            PickupKind::Sanctuary
+        }
+    }
+
+    fn glyph(&self) -> &'static str {
+        match self {
+            PickupKind::Heal => "癒",
+            PickupKind::LongSlash => "長",
+            PickupKind::QuickDraw => "速",
+            PickupKind::IronWill => "鉄",
+            PickupKind::Sanctuary => "祓",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            PickupKind::Heal => Color::srgb(0.5, 3.0, 0.5),
+            PickupKind::LongSlash => Color::srgb(3.0, 3.0, 3.0),
+            PickupKind::QuickDraw => Color::srgb(3.0, 3.0, 0.5),
+            PickupKind::IronWill => Color::srgb(1.0, 1.5, 3.0),
+            PickupKind::Sanctuary => Color::srgb(3.0, 1.0, 3.0),
+        }
+    }
+
+    /// How long a timed pickup lasts; zero for the ones that act instantly.
+    fn duration(&self) -> f32 {
+        match self {
+            PickupKind::LongSlash => 10.0,
+            PickupKind::QuickDraw => 10.0,
+            PickupKind::IronWill => 8.0,
+            _ => 0.0,
+        }
+    }
+
+    fn max_stacks(&self) -> u32 {
+        match self {
+            PickupKind::LongSlash => 3,
+            PickupKind::QuickDraw => 3,
+            PickupKind::IronWill => 2,
+            _ => 0,
+        }
+    }
+}
+
+pub struct PowerUp {
+    pub kind: PickupKind,
+    pub stacks: u32,
+    pub timer: Timer,
+}
+
+/// Timed power-ups on the player. Picking up one that is already running adds a stack up to its
+/// cap and restarts the timer.
+#[derive(Component, Default)]
+pub struct PowerUps {
+    pub active: Vec<PowerUp>,
+}
+
+impl PowerUps {
+    pub fn stacks(&self, kind: PickupKind) -> u32 {
+        self.active
+            .iter()
+            .find(|power_up| power_up.kind == kind)
+            .map_or(0, |power_up| power_up.stacks)
+    }
+
+    pub fn add(&mut self, kind: PickupKind) {
+        if let Some(power_up) = self
+            .active
+            .iter_mut()
+            .find(|power_up| power_up.kind == kind)
+        {
+            power_up.stacks = u32::min(power_up.stacks + 1, kind.max_stacks());
+            power_up.timer.reset();
+        } else {
+            self.active.push(PowerUp {
+                kind,
+                stacks: 1,
+                timer: Timer::from_seconds(kind.duration(), TimerMode::Once),
+            });
+        }
+    }
+
+    pub fn slash_distance(&self) -> f32 {
+        SLASH_DISTANCE * (1.0 + 0.25 * self.stacks(PickupKind::LongSlash) as f32)
+    }
+
+    pub fn slash_cooldown(&self) -> f32 {
+        SLASH_COOLDOWN * f32::powi(0.8, self.stacks(PickupKind::QuickDraw) as i32)
+    }
+
+    pub fn hit_cooldown(&self) -> f32 {
+        PLAYER_HIT_COOLDOWN + 0.5 * self.stacks(PickupKind::IronWill) as f32
+    }
+}
+
+#[derive(Component)]
+struct Pickup {
+    kind: PickupKind,
+    lifetime: Timer,
+}
+
+fn drop_pickups(
+    mut commands: Commands,
+    mut global: ResMut<GameGlobal>,
+    localizer: Localizer,
+    mut killed: EventReader<EnemyKilled>,
+) {
+    for kill in killed.read() {
+        if global.rand.gen::<f32>() >= DROP_CHANCE {
+            continue;
+        }
+        let kind = PickupKind::roll(global.rand.gen::<f32>());
+        commands.spawn((
+            Pickup {
+                kind,
+                lifetime: Timer::from_seconds(PICKUP_LIFETIME, TimerMode::Once),
+            },
+            // Physical so that collide_with_walls keeps drops inside the same bounds as everything
+            // else.
+            Physical {
+                top_speed: 0.0,
+                collider: BoundingCircle::new(kill.position.truncate(), 14.0),
+                wall_padding: 4.0,
+                ..default()
+            },
+            Text2dBundle {
+                text: Text::from_section(
+                    kind.glyph(),
+                    TextStyle {
+                        font: localizer.font(kind.glyph()),
+                        font_size: 40.0,
+                        color: kind.color(),
+                        ..default()
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                transform: Transform::from_translation(kill.position.truncate().extend(3.0)),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    mut sound: EventWriter<Sound>,
+    mut reset_presence: EventWriter<DarkPresenceReset>,
+    pickups: Query<(Entity, &Pickup, &Physical), Without<Player>>,
+    mut player: Query<(&Physical, &mut PlayerHealth, &mut PowerUps), With<Player>>,
+) {
+    if player.is_empty() {
+        return;
+    }
+    let (player_physical, mut health, mut power_ups) = player.single_mut();
+    for (entity, pickup, physical) in pickups.iter() {
+        if !physical.collider.intersects(&player_physical.collider) {
+            continue;
+        }
+        match pickup.kind {
+            PickupKind::Heal => health.heal(1),
+            PickupKind::Sanctuary => {
+                reset_presence.send(DarkPresenceReset);
+            }
+            kind => power_ups.add(kind),
+        }
+        sound.send(Sound {
+            id: SoundId::Finish,
+            position: physical.collider.center.extend(0.0),
+            speed: 1.5,
+        });
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn expire_pickups(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut pickups: Query<(Entity, &mut Pickup, &mut Text)>,
+) {
+    for (entity, mut pickup, mut text) in pickups.iter_mut() {
+        pickup.lifetime.tick(time.delta());
+        if pickup.lifetime.finished() {
+            commands.entity(entity).despawn_recursive();
+        } else if pickup.lifetime.remaining_secs() < 3.0 {
+            let wave = (pickup.lifetime.remaining_secs() * 8.0).sin();
+            let alpha = if settings.reduced_flashing {
+                // The same beat, breathing between the two instead of cutting.
+                0.625 + 0.375 * wave
+            } else if wave > 0.0 {
+                1.0
+            } else {
+                0.25
+            };
+            text.sections[0].style.color.set_alpha(alpha);
+        }
+    }
+}
+
+fn tick_power_ups(time: Res<Time>, mut power_ups: Query<&mut PowerUps>) {
+    for mut power_ups in power_ups.iter_mut() {
+        for power_up in power_ups.active.iter_mut() {
+            power_up.timer.tick(time.delta());
+        }
+        power_ups
+            .active
+            .retain(|power_up| !power_up.timer.finished());
+    }
+}
+
+fn destroy_pickups(mut commands: Commands, pickups: Query<Entity, With<Pickup>>) {
+    for pickup in pickups.iter() {
+        commands.entity(pickup).despawn_recursive();
+    }
+}
+
+#[derive(Component)]
+struct PowerUpHud;
+
+fn setup_power_up_hud(mut commands: Commands) {
+    commands.spawn((
+        PowerUpHud,
+        Text2dBundle {
+            text: Text::default().with_justify(JustifyText::Center),
+            transform: Transform::from_xyz(0.0, -455.0, 10.0),
+            ..default()
+        },
+    ));
+}
+
+fn update_power_up_hud(
+    localizer: Localizer,
+    state: Res<State<GameState>>,
+    power_ups: Query<&PowerUps>,
+    mut hud: Query<&mut Text, With<PowerUpHud>>,
+) {
+    if hud.is_empty() {
+        return;
+    }
+    let mut text = hud.single_mut();
+    text.sections.clear();
+    if *state.get() != GameState::Play {
+        return;
+    }
+    let Ok(power_ups) = power_ups.get_single() else {
+        return;
+    };
+    for power_up in power_ups.active.iter() {
+        let mut color = power_up.kind.color();
+        // Fade the icon over the last few seconds so it is clear when it is about to run out.
+        color.set_alpha(f32::min(power_up.timer.remaining_secs() / 3.0, 1.0));
+        let stacks = if power_up.stacks > 1 {
+            localizer.numeral(power_up.stacks)
+        } else {
+            String::new()
+        };
+        let icon = format!("{}{} ", power_up.kind.glyph(), stacks);
+        text.sections.push(TextSection::new(
+            icon.clone(),
+            TextStyle {
+                font: localizer.font(&icon),
+                font_size: 35.0,
+                color,
+                ..default()
+            },
+        ));
+    }
+}
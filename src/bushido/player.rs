@@ -1,18 +1,32 @@
 #![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
-use crate::bushido::menu::Hitcount;
-use crate::bushido::menu::Ichi;
-use crate::bushido::menu::Ni;
-use crate::bushido::menu::San;
-use crate::bushido::menu::Shi;
+use crate::bushido::assets::GameAssets;
+use crate::bushido::collision::ricochet;
+use crate::bushido::collision::Wall;
+use crate::bushido::draft::Upgrade;
+use crate::bushido::draft::Upgrades;
+use crate::bushido::locale::Localizer;
+use crate::bushido::menu::spawn_hitcount;
+use crate::bushido::pickup::PowerUps;
+use crate::bushido::settings::BufferAim;
+use crate::bushido::settings::Settings;
+use crate::bushido::settings::SlashMode;
+use crate::bushido::sound::Mixed;
+use crate::bushido::sound::Mixer;
+use crate::bushido::sound::SoundId;
 use crate::bushido::ActionState;
 use crate::bushido::Animate;
+use crate::bushido::DeathCause;
 use crate::bushido::GameState;
 use crate::bushido::InputModeManagerPlugin;
 use crate::bushido::Physical;
+use crate::bushido::PlayPhase;
+use crate::bushido::PlayerDied;
 use crate::bushido::Sound;
 use crate::bushido::SpriteAnimator;
 use crate::GameGlobal;
+use bevy::audio::Volume;
 use bevy::prelude::*;
+use bevy::time::Stopwatch;
 use bevy::utils::Duration;
 use leafwing_input_manager::prelude::*;
 
@@ -21,6 +35,28 @@ pub const SLASH_PAUSE: f32 = 0.66;
 pub const FINISH_TIME: f32 = 1.0;
 pub const SLASH_DISTANCE: f32 = 140.0;
 pub const SLASH_BOOST: f32 = 4.0;
+/// Half-width of the area a slash sweeps.
+pub const SLASH_WIDTH: f32 = 7.0;
+/// How much further than that the tip of a slash reaches enemies, for a 15 unit tip at the base
+/// width.
+pub const SLASH_TIP_REACH: f32 = 8.0;
+/// How many times a slash may bounce off the window border when ricochets are on.
+pub const RICOCHET_BOUNCES: u32 = 1;
+/// Holding longer than this turns a tap into a charge.
+pub const CHARGE_DELAY: f32 = 0.15;
+pub const CHARGE_TIME: f32 = 1.0;
+/// How far a full charge reaches, and how much longer it pauses, relative to a tap.
+pub const CHARGE_DISTANCE: f32 = 2.0;
+pub const CHARGE_PAUSE: f32 = 2.0;
+pub const PLAYER_MAX_HEALTH: u32 = 4;
+pub const PLAYER_HIT_COOLDOWN: f32 = 1.0;
+pub const DODGE_COOLDOWN: f32 = 1.2;
+pub const DODGE_TIME: f32 = 0.25;
+pub const DODGE_BOOST: f32 = 3.0;
+pub const PARRY_WINDOW: f32 = 0.2;
+pub const PARRY_RECOVERY: f32 = 0.6;
+pub const PLAYER_COLOR: Color = Color::srgb(4.0, 4.0, 4.0);
+pub const PARRY_COLOR: Color = Color::srgb(1.5, 2.5, 6.0);
 
 pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
@@ -29,7 +65,6 @@ impl Plugin for PlayerPlugin {
             .add_plugins(InputModeManagerPlugin)
             .init_resource::<ActionState<PlayerAction>>()
             .insert_resource(PlayerAction::default_input_map())
-            .init_state::<PlayerHits>()
             .add_systems(OnEnter(GameState::Play), (create_player, noise))
             .add_systems(OnExit(GameState::Play), remove_noise)
             .add_systems(
@@ -37,16 +72,17 @@ impl Plugin for PlayerPlugin {
                 (
                     update_player,
                     player_hit.after(update_player),
+                    death_by_wounds.after(player_hit),
                     player_sprite_states,
+                    draw_charge_reticle.after(update_player),
                 )
-                    .run_if(in_state(GameState::Play)),
+                    .run_if(in_state(PlayPhase::Fighting)),
             )
             .add_systems(
                 Update,
                 player_sprite_states.run_if(in_state(GameState::GameOver)),
             )
             .add_systems(OnExit(GameState::GameOver), destroy_player)
-            .add_systems(OnExit(GameState::DarkPresenceAttack), destroy_player)
             .add_event::<PlayerHit>()
             .add_event::<Slash>()
             .add_event::<Finish>();
@@ -67,6 +103,23 @@ pub struct PlayerCooldowns {
     pub slash: Timer,
     pub pause: Timer,
     pub finish: Timer,
+    pub dodge: Timer,
+    /// Runs while a dodge is under way; the player can't be hit until it finishes.
+    pub roll: Timer,
+    /// The parry window. Missing it, with no contact while it runs, costs `recovery`.
+    pub parry: Timer,
+    pub recovery: Timer,
+    pub parried: bool,
+    /// Set while `Slash` is held in the charged mode.
+    pub charging: bool,
+    pub charge: Stopwatch,
+    /// Set while an early press waits for the slash to become ready, until `buffer` runs out.
+    pub buffered: bool,
+    pub buffer: Timer,
+    /// The aim a buffered press locked in, when aiming at the press is selected.
+    pub locked_aim: Option<Vec2>,
+    /// Slash charges banked on top of the one the cooldown timer holds.
+    pub spare: u32,
 }
 
 impl Default for PlayerCooldowns {
@@ -75,6 +128,17 @@ impl Default for PlayerCooldowns {
             slash: Timer::new(Duration::from_secs_f32(SLASH_COOLDOWN), TimerMode::Once),
             pause: Timer::new(Duration::from_secs_f32(SLASH_PAUSE), TimerMode::Once),
             finish: Timer::new(Duration::from_secs_f32(FINISH_TIME), TimerMode::Once),
+            dodge: Timer::new(Duration::from_secs_f32(DODGE_COOLDOWN), TimerMode::Once),
+            roll: Timer::new(Duration::from_secs_f32(DODGE_TIME), TimerMode::Once),
+            parry: Timer::new(Duration::from_secs_f32(PARRY_WINDOW), TimerMode::Once),
+            recovery: Timer::new(Duration::from_secs_f32(PARRY_RECOVERY), TimerMode::Once),
+            parried: false,
+            charging: false,
+            charge: Stopwatch::new(),
+            buffered: false,
+            buffer: Timer::default(),
+            locked_aim: None,
+            spare: 0,
         };
         cooldowns
             .slash
@@ -86,6 +150,58 @@ impl Default for PlayerCooldowns {
             .finish
             .set_elapsed(Duration::from_secs_f32(FINISH_TIME));
         cooldowns
+            .dodge
+            .set_elapsed(Duration::from_secs_f32(DODGE_COOLDOWN));
+        cooldowns
+            .roll
+            .set_elapsed(Duration::from_secs_f32(DODGE_TIME));
+        cooldowns
+            .parry
+            .set_elapsed(Duration::from_secs_f32(PARRY_WINDOW));
+        cooldowns
+            .recovery
+            .set_elapsed(Duration::from_secs_f32(PARRY_RECOVERY));
+        cooldowns
+    }
+}
+
+impl PlayerCooldowns {
+    pub fn invulnerable(&self) -> bool {
+        !self.roll.finished()
+    }
+
+    pub fn parrying(&self) -> bool {
+        !self.parry.finished()
+    }
+
+    /// Whether the player is free to start a slash, dodge or parry.
+    fn recovered(&self) -> bool {
+        self.parry.finished() && self.recovery.finished()
+    }
+
+    fn slash_ready(&self) -> bool {
+        (self.slash.finished() || self.spare > 0) && self.finish.finished() && self.recovered()
+    }
+
+    /// Seconds until `slash_ready` turns true, if nothing else happens in the meantime.
+    fn time_until_slash(&self) -> f32 {
+        let slash = if self.slash.finished() || self.spare > 0 {
+            0.0
+        } else {
+            self.slash.remaining_secs()
+        };
+        let finish = if !self.pause.finished() {
+            self.pause.remaining_secs() + self.finish.duration().as_secs_f32()
+        } else {
+            self.finish.remaining_secs()
+        };
+        let recovery = self.parry.remaining_secs() + self.recovery.remaining_secs();
+        f32::max(slash, f32::max(finish, recovery))
+    }
+
+    /// How far along the charge is, from 0 for a tap to 1 for a full charge.
+    pub fn charge_fraction(&self) -> f32 {
+        ((self.charge.elapsed_secs() - CHARGE_DELAY) / CHARGE_TIME).clamp(0.0, 1.0)
     }
 }
 
@@ -99,9 +215,44 @@ struct PlayerBundle {
     moving: PlayerMoving,
     movement: Physical,
     cooldowns: PlayerCooldowns,
+    health: PlayerHealth,
+    power_ups: PowerUps,
     listener: SpatialListener,
 }
 
+#[derive(Component)]
+pub struct PlayerHealth {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl PlayerHealth {
+    pub fn new(max: u32) -> PlayerHealth {
+        PlayerHealth { current: max, max }
+    }
+
+    pub fn hits_taken(&self) -> u32 {
+        self.max - self.current
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current == 0
+    }
+
+    pub fn hurt(&mut self) {
+        self.current = self.current.saturating_sub(1);
+    }
+
+    pub fn heal(&mut self, amount: u32) {
+        self.current = u32::min(self.current + amount, self.max);
+    }
+
+    pub fn raise_max(&mut self, amount: u32) {
+        self.max += amount;
+        self.current += amount;
+    }
+}
+
 #[derive(Component)]
 struct PlayerTopSprite;
 
@@ -113,6 +264,8 @@ pub enum PlayerTopState {
     Idle,
     Slash,
     Finish,
+    Dodge,
+    Parry,
     Dead,
 }
 
@@ -134,28 +287,7 @@ pub enum PlayerMoving {
     Right,
 }
 
-pub fn create_player(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
-) {
-    let top_texture = asset_server.load("embedded://PlayerTopQuartered.png");
-    let top_layout = layouts.add(TextureAtlasLayout::from_grid(
-        UVec2::splat(20),
-        5,
-        1,
-        None,
-        None,
-    ));
-    let bottom_texture = asset_server.load("embedded://PlayerBottom.png");
-    let bottom_layout = layouts.add(TextureAtlasLayout::from_grid(
-        UVec2::splat(20),
-        5,
-        1,
-        None,
-        None,
-    ));
-
+pub fn create_player(mut commands: Commands, assets: Res<GameAssets>) {
     commands
         .spawn(PlayerBundle {
             player: Player,
@@ -169,6 +301,8 @@ pub fn create_player(
             moving: PlayerMoving::Left,
             movement: Physical::default(),
             cooldowns: PlayerCooldowns::default(),
+            health: PlayerHealth::new(PLAYER_MAX_HEALTH),
+            power_ups: PowerUps::default(),
             listener: SpatialListener::new(40.0),
         })
         .with_children(|commands| {
@@ -177,16 +311,16 @@ pub fn create_player(
                 SpriteAnimator {
                     sprite: SpriteBundle {
                         sprite: Sprite {
-                            color: Color::srgb(4.0, 4.0, 4.0),
+                            color: PLAYER_COLOR,
                             ..default()
                         },
-                        texture: top_texture,
+                        texture: assets.player_top.clone(),
                         transform: Transform::from_scale(Vec3::splat(2.0))
                             .with_translation(Vec3::new(0.0, 0.0, 2.0)),
                         ..default()
                     },
                     layout: TextureAtlas {
-                        layout: top_layout,
+                        layout: assets.player_layout.clone(),
                         index: 0,
                     },
                     animation: Animate {
@@ -203,13 +337,13 @@ pub fn create_player(
                 PlayerBottomSprite,
                 SpriteAnimator {
                     sprite: SpriteBundle {
-                        texture: bottom_texture,
+                        texture: assets.player_bottom.clone(),
                         transform: Transform::from_scale(Vec3::splat(2.0))
                             .with_translation(Vec3::new(0.0, 0.0, 1.0)),
                         ..default()
                     },
                     layout: TextureAtlas {
-                        layout: bottom_layout,
+                        layout: assets.player_layout.clone(),
                         index: 0,
                     },
                     animation: Animate {
@@ -225,23 +359,21 @@ pub fn create_player(
         });
 }
 
-fn destroy_player(
-    mut commands: Commands,
-    player: Query<Entity, With<Player>>,
-    mut next_hit: ResMut<NextState<PlayerHits>>,
-) {
+fn destroy_player(mut commands: Commands, player: Query<Entity, With<Player>>) {
     let player = player.single();
     commands.entity(player).despawn_recursive();
-    next_hit.set(PlayerHits::Zero)
 }
 
 fn update_player(
     time: Res<Time>,
     global: ResMut<GameGlobal>,
+    upgrades: Res<Upgrades>,
+    settings: Res<Settings>,
     action_state: Res<ActionState<PlayerAction>>,
     mut play_sounds: EventWriter<Sound>,
     mut slash_event: EventWriter<Slash>,
     mut finish_event: EventWriter<Finish>,
+    walls: Query<&Wall>,
     mut player: Query<
         (
             &mut Transform,
@@ -251,10 +383,10 @@ fn update_player(
             &mut PlayerMoving,
             &mut Physical,
             &mut PlayerCooldowns,
+            &PowerUps,
         ),
         With<Player>,
     >,
-    noise_stuff: Query<&AudioSink, With<Noise>>,
 ) {
     let (
         mut transform,
@@ -264,14 +396,39 @@ fn update_player(
         mut moving,
         mut physical,
         mut cooldowns,
+        power_ups,
     ) = player.single_mut();
     let delta = time.delta_seconds();
+    let slash_distance = power_ups.slash_distance();
+    let slash_width = SLASH_WIDTH * (1.0 + 0.6 * upgrades.stacks(Upgrade::WideSlash) as f32);
+
+    cooldowns
+        .slash
+        .set_duration(Duration::from_secs_f32(power_ups.slash_cooldown()));
+    physical
+        .hit_cooldown
+        .set_duration(Duration::from_secs_f32(power_ups.hit_cooldown()));
 
     cooldowns.slash.tick(time.delta());
     cooldowns.pause.tick(time.delta());
     cooldowns.finish.tick(time.delta());
+    cooldowns.dodge.tick(time.delta());
+    cooldowns.roll.tick(time.delta());
+    cooldowns.parry.tick(time.delta());
+    cooldowns.recovery.tick(time.delta());
     physical.hit_cooldown.tick(time.delta());
 
+    if cooldowns.parry.just_finished() {
+        if !cooldowns.parried {
+            cooldowns.recovery.reset();
+        }
+        top_state.set_if_neq(PlayerTopState::Idle);
+    }
+
+    if cooldowns.roll.just_finished() && *top_state == PlayerTopState::Dodge {
+        top_state.set_if_neq(PlayerTopState::Idle);
+    }
+
     if cooldowns.slash.just_finished() {
         if !cooldowns.slash.finished() {
             top_state.set_if_neq(PlayerTopState::Finish);
@@ -280,6 +437,12 @@ fn update_player(
         }
     }
 
+    // A ready charge gets banked and the timer restarts until every extra charge is stored.
+    if cooldowns.slash.finished() && cooldowns.spare < upgrades.stacks(Upgrade::SecondCharge) {
+        cooldowns.spare += 1;
+        cooldowns.slash.reset();
+    }
+
     if !cooldowns.pause.finished() {
         top_state.set_if_neq(PlayerTopState::Slash);
         bottom_state.set_if_neq(PlayerBottomState::Idle);
@@ -293,59 +456,191 @@ fn update_player(
     if cooldowns.finish.just_finished() {
         top_state.set_if_neq(PlayerTopState::Idle);
         play_sounds.send(Sound {
-            name: "finish".to_string(),
+            id: SoundId::Finish,
             position: transform.translation,
             speed: 1.0,
         });
         finish_event.send(Finish);
     }
 
+    // A press just before the slash is ready is held on to and goes off on the first frame it can.
+    let mut start = false;
     if action_state.just_pressed(&PlayerAction::Slash) {
-        if cooldowns.slash.finished() & cooldowns.finish.finished() {
+        if cooldowns.slash_ready() {
+            start = true;
+        } else if cooldowns.time_until_slash() <= settings.slash_buffer {
+            cooldowns.buffered = true;
+            cooldowns.buffer = Timer::from_seconds(settings.slash_buffer, TimerMode::Once);
+            if settings.buffer_aim == BufferAim::Press {
+                cooldowns.locked_aim =
+                    Some(aim_direction(&action_state, &global, &transform, &physical));
+            }
+        } else {
             play_sounds.send(Sound {
-                name: "slash".to_string(),
+                id: SoundId::Unready,
                 position: transform.translation,
                 speed: 1.0,
             });
-            top_state.set_if_neq(PlayerTopState::Slash);
+        }
+    } else if cooldowns.buffered {
+        cooldowns.buffer.tick(time.delta());
+        if cooldowns.slash_ready() {
+            cooldowns.buffered = false;
+            start = true;
+        } else if cooldowns.buffer.finished() {
+            cooldowns.buffered = false;
+            cooldowns.locked_aim = None;
+        }
+    }
+
+    // In the charged mode the slash goes off on release instead, so a tap still lands as before.
+    let mut release = None;
+    if start {
+        if settings.slash_mode == SlashMode::Charge && action_state.pressed(&PlayerAction::Slash) {
+            cooldowns.charging = true;
+            cooldowns.charge.reset();
+        } else {
+            release = Some(0.0);
+        }
+    }
+    if cooldowns.charging {
+        cooldowns.charge.tick(time.delta());
+        if !action_state.pressed(&PlayerAction::Slash) {
+            cooldowns.charging = false;
+            if cooldowns.slash_ready() {
+                release = Some(cooldowns.charge_fraction());
+            } else {
+                cooldowns.locked_aim = None;
+            }
+        }
+    }
+
+    if let Some(charge) = release {
+        let slash_distance = slash_distance * (1.0 + (CHARGE_DISTANCE - 1.0) * charge);
+        play_sounds.send(Sound {
+            id: SoundId::Slash,
+            position: transform.translation,
+            speed: 1.0,
+        });
+        top_state.set_if_neq(PlayerTopState::Slash);
+        if cooldowns.slash.finished() {
             cooldowns.slash.reset();
+        } else {
+            cooldowns.spare -= 1;
+        }
+
+        let direction = cooldowns
+            .locked_aim
+            .take()
+            .unwrap_or_else(|| aim_direction(&action_state, &global, &transform, &physical));
+
+        let pause = Duration::from_secs_f32(SLASH_PAUSE * (1.0 + (CHARGE_PAUSE - 1.0) * charge));
+        if !cooldowns.pause.finished() {
+            cooldowns.pause.set_elapsed(pause);
+        }
+        cooldowns.pause.set_duration(pause);
+
+        let mut slash_direction =
+            Dir2::from_xy(direction.x, direction.y).unwrap_or(Dir2::from_xy(1.0, 0.0).unwrap());
+
+        // With ricochets on, the rest of the slash reflects off the window border and every leg
+        // is a slash of its own. Obstacles still just stop it. Only the last leg counts as
+        // blocked, so bouncing isn't a free refund.
+        let blocking: Vec<&Wall> = walls
+            .iter()
+            .filter(|wall| wall.kind.stops_slash(global.expanded))
+            .collect();
+        let bounces = if settings.ricochet {
+            RICOCHET_BOUNCES
+        } else {
+            0
+        };
+        let legs = ricochet(
+            transform.translation.truncate(),
+            slash_direction,
+            slash_distance,
+            physical.collider.radius() + physical.wall_padding,
+            &blocking,
+            bounces,
+        );
+        for (index, leg) in legs.iter().enumerate() {
+            let last = index + 1 == legs.len();
+            slash_event.send(Slash {
+                start: leg.start,
+                direction: leg.direction,
+                length: leg.length,
+                width: slash_width,
+                blocked: last && leg.normal.is_some(),
+                bounced: !last,
+            });
+            transform.translation = leg.end().extend(transform.translation.z);
+            slash_direction = leg.direction;
+        }
 
+        let boost = *slash_direction * physical.top_speed * SLASH_BOOST;
+        physical.impulse(boost);
+    }
+
+    // No dodging out of a slash: the pause and the finisher both have to play out first.
+    if action_state.just_pressed(&PlayerAction::Dodge) {
+        if cooldowns.dodge.finished()
+            & cooldowns.pause.finished()
+            & cooldowns.finish.finished()
+            & cooldowns.recovered()
+        {
             let direction;
 
-            if action_state.axis_pair(&PlayerAction::StickAim) != Vec2::ZERO {
+            if action_state.axis_pair(&PlayerAction::Run) != Vec2::ZERO {
                 direction = action_state
-                    .clamped_axis_pair(&PlayerAction::StickAim)
+                    .clamped_axis_pair(&PlayerAction::Run)
                     .xy()
                     .normalize_or_zero();
-            } else if global.gamepad {
-                direction = physical.velocity.normalize_or_zero()
+            } else if physical.velocity != Vec2::ZERO {
+                direction = physical.velocity.normalize_or_zero();
             } else {
-                direction =
-                    (global.cursor_position - transform.translation.truncate()).normalize_or_zero();
-            }
-
-            if !cooldowns.pause.finished() {
-                cooldowns
-                    .pause
-                    .set_elapsed(Duration::from_secs_f32(SLASH_PAUSE));
+                match *facing {
+                    PlayerFacing::Left => direction = Vec2::NEG_X,
+                    PlayerFacing::Right => direction = Vec2::X,
+                }
             }
 
-            let slash_start = transform.translation;
-            transform.translation += (direction * SLASH_DISTANCE).extend(0.0);
-            let slash_end = transform.translation;
-
-            slash_event.send(Slash {
-                start: slash_start.truncate(),
-                direction: Dir2::from_xy(direction.x, direction.y)
-                    .unwrap_or(Dir2::from_xy(1.0, 0.0).unwrap()),
-                length: SLASH_DISTANCE,
+            play_sounds.send(Sound {
+                id: SoundId::Vrrp,
+                position: transform.translation,
+                speed: 1.5,
             });
+            top_state.set_if_neq(PlayerTopState::Dodge);
+            cooldowns.dodge.reset();
+            cooldowns.roll.reset();
 
-            let boost = direction * physical.top_speed * SLASH_BOOST;
+            let boost = direction * physical.top_speed * DODGE_BOOST;
             physical.impulse(boost);
         } else {
             play_sounds.send(Sound {
-                name: "unready".to_string(),
+                id: SoundId::Unready,
+                position: transform.translation,
+                speed: 1.0,
+            });
+        }
+    }
+
+    if action_state.just_pressed(&PlayerAction::Parry) {
+        if cooldowns.pause.finished()
+            & cooldowns.finish.finished()
+            & cooldowns.roll.finished()
+            & cooldowns.recovered()
+        {
+            play_sounds.send(Sound {
+                id: SoundId::Stance,
+                position: transform.translation,
+                speed: 1.0,
+            });
+            top_state.set_if_neq(PlayerTopState::Parry);
+            cooldowns.parry.reset();
+            cooldowns.parried = false;
+        } else {
+            play_sounds.send(Sound {
+                id: SoundId::Unready,
                 position: transform.translation,
                 speed: 1.0,
             });
@@ -394,22 +689,56 @@ fn update_player(
     } else {
         bottom_state.set_if_neq(PlayerBottomState::Idle);
     }
+}
 
-    if let Ok(sink) = noise_stuff.get_single() {
-        if f32::abs(transform.translation.x) > global.inner_world_size.x / 2.0
-            || f32::abs(transform.translation.y) > global.inner_world_size.y / 2.0
-        {
-            sink.play()
-        } else {
-            sink.pause()
-        }
+fn aim_direction(
+    action_state: &ActionState<PlayerAction>,
+    global: &GameGlobal,
+    transform: &Transform,
+    physical: &Physical,
+) -> Vec2 {
+    if action_state.axis_pair(&PlayerAction::StickAim) != Vec2::ZERO {
+        action_state
+            .clamped_axis_pair(&PlayerAction::StickAim)
+            .xy()
+            .normalize_or_zero()
+    } else if global.gamepad {
+        physical.velocity.normalize_or_zero()
+    } else {
+        (global.cursor_position - transform.translation.truncate()).normalize_or_zero()
+    }
+}
+
+/// Shows how far the held slash will reach; it only appears once a tap has become a charge.
+fn draw_charge_reticle(
+    mut gizmos: Gizmos,
+    global: Res<GameGlobal>,
+    action_state: Res<ActionState<PlayerAction>>,
+    player: Query<(&Transform, &Physical, &PlayerCooldowns, &PowerUps), With<Player>>,
+) {
+    let Ok((transform, physical, cooldowns, power_ups)) = player.get_single() else {
+        return;
+    };
+    if !cooldowns.charging || cooldowns.charge.elapsed_secs() < CHARGE_DELAY {
+        return;
     }
+    let charge = cooldowns.charge_fraction();
+    let direction = cooldowns
+        .locked_aim
+        .unwrap_or_else(|| aim_direction(&action_state, &global, transform, physical));
+    let start = transform.translation.truncate();
+    let end =
+        start + direction * power_ups.slash_distance() * (1.0 + (CHARGE_DISTANCE - 1.0) * charge);
+    let color = Color::srgba(2.5, 0.25, 0.25, 0.3 + 0.7 * charge);
+    gizmos.line_2d(start, end, color);
+    gizmos.circle_2d(end, 6.0 + 10.0 * charge, color);
 }
 
 #[derive(Component)]
-struct Noise;
+pub struct Noise;
 
-fn noise(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn noise(mut commands: Commands, assets: Res<GameAssets>, mixer: Res<Mixer>) {
+    let mixed = Mixed::of(SoundId::Noise);
     commands.spawn((
         Noise,
         SpatialBundle {
@@ -417,9 +746,13 @@ fn noise(mut commands: Commands, asset_server: Res<AssetServer>) {
             ..default()
         },
         AudioBundle {
-            source: asset_server.load("embedded://noise.mp3"),
-            settings: PlaybackSettings::LOOP.with_spatial(false).paused(),
+            source: assets.sound(SoundId::Noise),
+            settings: PlaybackSettings::LOOP
+                .with_spatial(false)
+                .with_volume(Volume::new(mixed.level(&mixer)))
+                .paused(),
         },
+        mixed,
     ));
 }
 
@@ -431,6 +764,7 @@ fn remove_noise(mut commands: Commands, noise: Query<Entity, With<Noise>>) {
 
 fn player_sprite_states(
     time: Res<Time>,
+    mut cold: Local<bool>,
     top_state: Query<(&PlayerTopState, Ref<PlayerTopState>)>,
     bottom_state: Query<(&PlayerBottomState, Ref<PlayerBottomState>)>,
     facing: Query<&PlayerFacing>,
@@ -476,6 +810,18 @@ fn player_sprite_states(
                     anim.speed = 0.0;
                     anim.offset = 0.0;
                 }
+                PlayerTopState::Dodge => {
+                    anim.first = 0;
+                    anim.last = 1;
+                    anim.speed = 12.0;
+                    anim.offset = 0.0;
+                }
+                PlayerTopState::Parry => {
+                    anim.first = 2;
+                    anim.last = 2;
+                    anim.speed = 0.0;
+                    anim.offset = 0.0;
+                }
                 PlayerTopState::Dead => {
                     anim.first = 4;
                     anim.last = 4;
@@ -483,6 +829,19 @@ fn player_sprite_states(
                     anim.offset = 0.0;
                 }
             }
+            // Fade out while rolling so the i-frames are readable, and go cold while parrying.
+            // The colour is only swapped going into and out of the parry, so anything else
+            // tinting the sprite keeps its colour the rest of the time.
+            let parrying = *top_state == PlayerTopState::Parry;
+            if parrying != *cold {
+                *cold = parrying;
+                sprite.color = if parrying { PARRY_COLOR } else { PLAYER_COLOR };
+            }
+            if *top_state == PlayerTopState::Dodge {
+                sprite.color.set_alpha(0.35);
+            } else {
+                sprite.color.set_alpha(1.0);
+            }
         } else if *top_state == PlayerTopState::Idle {
             match bottom_state {
                 PlayerBottomState::Idle => {
@@ -586,6 +945,8 @@ pub enum PlayerAction {
     Run,
     StickAim,
     Slash,
+    Dodge,
+    Parry,
 }
 
 impl Actionlike for PlayerAction {
@@ -597,6 +958,8 @@ impl Actionlike for PlayerAction {
             PlayerAction::Run => InputControlKind::DualAxis,
             PlayerAction::StickAim => InputControlKind::DualAxis,
             PlayerAction::Slash => InputControlKind::Button,
+            PlayerAction::Dodge => InputControlKind::Button,
+            PlayerAction::Parry => InputControlKind::Button,
         }
     }
 }
@@ -613,6 +976,13 @@ impl PlayerAction {
         input_map.insert(PlayerAction::Slash, GamepadButtonType::LeftTrigger2);
         input_map.insert(PlayerAction::Slash, GamepadButtonType::RightThumb);
         input_map.insert(PlayerAction::Slash, MouseButton::Left);
+        input_map.insert(PlayerAction::Dodge, GamepadButtonType::South);
+        input_map.insert(PlayerAction::Dodge, GamepadButtonType::LeftThumb);
+        input_map.insert(PlayerAction::Dodge, KeyCode::Space);
+        input_map.insert(PlayerAction::Dodge, MouseButton::Right);
+        input_map.insert(PlayerAction::Parry, GamepadButtonType::East);
+        input_map.insert(PlayerAction::Parry, KeyCode::ShiftLeft);
+        input_map.insert(PlayerAction::Parry, KeyCode::KeyE);
         input_map
     }
 }
@@ -622,88 +992,76 @@ pub struct Slash {
     pub start: Vec2,
     pub direction: Dir2,
     pub length: f32,
+    /// Half-width of the swept capsule, so weapons and upgrades can widen or narrow the hit.
+    pub width: f32,
+    /// Whether a wall cut the slash short of its full reach.
+    pub blocked: bool,
+    /// This leg ended against a wall it reflected off into the next one, so touching that wall
+    /// isn't a miss.
+    pub bounced: bool,
 }
 
 #[derive(Event)]
 pub struct Finish;
 
-#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
-pub enum PlayerHits {
-    #[default]
-    Zero,
-    Ichi,
-    Ni,
-    San,
-    Shi,
-}
-
 #[derive(Event)]
 pub struct PlayerHit;
 
 fn player_hit(
+    mut commands: Commands,
+    localizer: Localizer,
+    settings: Res<Settings>,
     mut hit: EventReader<PlayerHit>,
-    mut hitcounts: Query<(
-        Option<&Ichi>,
-        Option<&Ni>,
-        Option<&San>,
-        Option<&Shi>,
-        &mut Hitcount,
-    )>,
-    current_hit: Res<State<PlayerHits>>,
-    mut next_hit: ResMut<NextState<PlayerHits>>,
-    mut game_state: ResMut<NextState<GameState>>,
+    mut died: EventWriter<PlayerDied>,
     mut play_sounds: EventWriter<Sound>,
-    player_pos: Query<&Transform, With<Player>>,
-    mut top_query: Query<&mut PlayerTopState>,
-    mut bottom_query: Query<&mut PlayerBottomState>,
+    mut player: Query<(&Transform, &mut PlayerHealth), With<Player>>,
 ) {
+    if player.is_empty() {
+        return;
+    }
+    let (transform, mut health) = player.single_mut();
     for event in hit.read() {
-        for (ichi, ni, san, shi, mut hitcount) in hitcounts.iter_mut() {
-            if *current_hit.get() == PlayerHits::Zero && ichi.is_some() {
-                next_hit.set(PlayerHits::Ichi);
-                play_sounds.send(Sound {
-                    name: "hurt".to_string(),
-                    position: player_pos.single().translation,
-                    speed: 1.0,
-                });
-                hitcount.timer.reset();
-                break;
-            }
-            if *current_hit.get() == PlayerHits::Ichi && ni.is_some() {
-                next_hit.set(PlayerHits::Ni);
-                play_sounds.send(Sound {
-                    name: "hurt".to_string(),
-                    position: player_pos.single().translation,
-                    speed: 0.8,
-                });
-                hitcount.timer.reset();
-                break;
-            }
-            if *current_hit.get() == PlayerHits::Ni && san.is_some() {
-                next_hit.set(PlayerHits::San);
-                play_sounds.send(Sound {
-                    name: "hurt".to_string(),
-                    position: player_pos.single().translation,
-                    speed: 0.6,
-                });
-                hitcount.timer.reset();
-                break;
-            }
-            if *current_hit.get() == PlayerHits::San && shi.is_some() {
-                next_hit.set(PlayerHits::Shi);
-                game_state.set(GameState::GameOver);
-                play_sounds.send(Sound {
-                    name: "dead".to_string(),
-                    position: player_pos.single().translation,
-                    speed: 1.0,
-                });
-                let mut top = top_query.single_mut();
-                let mut bottom = bottom_query.single_mut();
-                top.set_if_neq(PlayerTopState::Dead);
-                bottom.set_if_neq(PlayerBottomState::Idle);
-                hitcount.timer.reset();
-                break;
-            }
+        if health.is_dead() {
+            break;
+        }
+        health.hurt();
+        spawn_hitcount(
+            &mut commands,
+            &localizer,
+            health.hits_taken(),
+            health.is_dead(),
+            settings.reduced_flashing,
+        );
+        if health.is_dead() {
+            died.send(PlayerDied {
+                cause: DeathCause::Wounds,
+            });
+        } else {
+            play_sounds.send(Sound {
+                id: SoundId::Hurt,
+                position: transform.translation,
+                speed: f32::max(1.2 - 0.2 * health.hits_taken() as f32, 0.4),
+            });
         }
     }
 }
+
+fn death_by_wounds(
+    mut died: EventReader<PlayerDied>,
+    mut play_sounds: EventWriter<Sound>,
+    mut player: Query<(&Transform, &mut PlayerTopState, &mut PlayerBottomState), With<Player>>,
+) {
+    for death in died.read() {
+        if death.cause != DeathCause::Wounds || player.is_empty() {
+            continue;
+        }
+        let (transform, mut top, mut bottom) = player.single_mut();
+        play_sounds.send(Sound {
+            id: SoundId::Dead,
+            position: transform.translation,
+            speed: 1.0,
+        });
+        top.set_if_neq(PlayerTopState::Dead);
+        bottom.set_if_neq(PlayerBottomState::Idle);
+    }
+}
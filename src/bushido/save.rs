@@ -0,0 +1,100 @@
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The few values kept between launches, as `key = value` lines in the player's data folder.
+/// Nothing breaks when there's nowhere to write it; the game just starts fresh every time.
+#[derive(Resource, Default)]
+pub struct SaveFile {
+    path: Option<PathBuf>,
+    values: HashMap<String, String>,
+}
+
+impl SaveFile {
+    pub fn load() -> SaveFile {
+        let path = save_path();
+        let values = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| parse_save(&text))
+            .unwrap_or_default();
+        SaveFile { path, values }
+    }
+
+    pub fn get<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.values.get(key)?.parse().ok()
+    }
+
+    pub fn set(&mut self, key: &str, value: impl ToString) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+
+    pub fn write(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Some(folder) = path.parent() {
+            if let Err(error) = fs::create_dir_all(folder) {
+                warn!("Couldn't make the save folder {:?}: {}", folder, error);
+                return;
+            }
+        }
+        if let Err(error) = fs::write(path, write_save(&self.values)) {
+            warn!("Couldn't write the save file {:?}: {}", path, error);
+        }
+    }
+}
+
+/// Where each platform keeps per-user game data.
+fn save_path() -> Option<PathBuf> {
+    let folder = if cfg!(target_os = "windows") {
+        PathBuf::from(env::var_os("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(env::var_os("HOME")?).join("Library/Application Support")
+    } else if let Some(data) = env::var_os("XDG_DATA_HOME") {
+        PathBuf::from(data)
+    } else {
+        PathBuf::from(env::var_os("HOME")?).join(".local/share")
+    };
+    Some(folder.join("BushidoBlazer").join("save.txt"))
+}
+
+/// Lines without an `=` are skipped, so a damaged file loses only what's damaged.
+fn parse_save(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+fn write_save(values: &HashMap<String, String>) -> String {
+    let mut keys = values.keys().collect::<Vec<_>>();
+    keys.sort();
+    keys.into_iter()
+        .map(|key| format!("{} = {}\n", key, values[key]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_skips_damage() {
+        let mut values = HashMap::default();
+        values.insert("best_kills".to_string(), "12".to_string());
+        values.insert("reduced_flashing".to_string(), "true".to_string());
+        let text = write_save(&values) + "garbage\n";
+        assert_eq!(parse_save(&text), values);
+
+        let save = SaveFile { path: None, values };
+        assert_eq!(save.get::<usize>("best_kills"), Some(12));
+        assert_eq!(save.get::<bool>("reduced_flashing"), Some(true));
+        assert_eq!(save.get::<usize>("reduced_flashing"), None);
+        assert_eq!(save.get::<usize>("runs"), None);
+    }
+}
@@ -0,0 +1,384 @@
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use crate::bushido::arena::ARENAS;
+use crate::bushido::assets::GameAssets;
+use crate::bushido::draft::Draft;
+use crate::bushido::locale::Language;
+use crate::bushido::locale::Localized;
+use crate::bushido::locale::Localizer;
+use crate::bushido::player::PlayerAction;
+use crate::bushido::sound::Mixer;
+use crate::bushido::sound::SoundId;
+use crate::bushido::ActionState;
+use crate::bushido::GameState;
+use crate::bushido::Sound;
+use crate::GameGlobal;
+use bevy::prelude::*;
+
+pub struct SettingsPlugin;
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::default())
+            .insert_resource(SettingsCursor::default())
+            .add_systems(Startup, setup_settings_menu)
+            .add_systems(OnEnter(GameState::Menu), show_settings)
+            .add_systems(OnEnter(GameState::Play), hide_settings)
+            .add_systems(
+                Update,
+                (
+                    navigate_settings,
+                    update_settings_menu.after(navigate_settings),
+                )
+                    .run_if(in_state(GameState::Menu)),
+            )
+            .add_systems(Update, apply_settings);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum SlashMode {
+    /// The slash goes off as soon as the button is pressed.
+    #[default]
+    Tap,
+    /// Holding the button charges a longer slash that goes off on release.
+    Charge,
+}
+
+/// Which aim a buffered slash uses: the one at the moment of the press, or the one when it goes
+/// off.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum BufferAim {
+    Press,
+    #[default]
+    Release,
+}
+
+/// The choices for how early a slash press may come and still be kept.
+const SLASH_BUFFERS: [f32; 4] = [0.0, 0.1, 0.2, 0.3];
+
+/// Options picked on the title screen. Other resources take their copy from here whenever it
+/// changes, see `apply_settings`. Volumes are set on the `Mixer` directly.
+#[derive(Resource)]
+pub struct Settings {
+    pub language: Language,
+    pub slash_mode: SlashMode,
+    /// In seconds.
+    pub slash_buffer: f32,
+    pub buffer_aim: BufferAim,
+    /// Slashes that hit the window border bounce off it instead of stopping.
+    pub ricochet: bool,
+    /// Low-passes sounds from outside the inner window.
+    pub muffle: bool,
+    /// Caps bloom and swaps flicker and flashes for slow fades. Asked about on launch.
+    pub reduced_flashing: bool,
+    /// 0 is the open field, otherwise one past the index into `ARENAS`.
+    pub arena: usize,
+    pub draft: bool,
+    pub daily: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            language: Language::default(),
+            slash_mode: SlashMode::default(),
+            slash_buffer: 0.1,
+            buffer_aim: BufferAim::default(),
+            ricochet: false,
+            muffle: true,
+            reduced_flashing: false,
+            arena: 0,
+            draft: false,
+            daily: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Setting {
+    Language,
+    SlashMode,
+    SlashBuffer,
+    BufferAim,
+    Ricochet,
+    Arena,
+    Draft,
+    Daily,
+    Muffle,
+    ReducedFlashing,
+    MasterVolume,
+    EffectsVolume,
+    InterfaceVolume,
+    AmbienceVolume,
+    MusicVolume,
+}
+
+impl Setting {
+    const ALL: [Setting; 15] = [
+        Setting::Language,
+        Setting::SlashMode,
+        Setting::SlashBuffer,
+        Setting::BufferAim,
+        Setting::Ricochet,
+        Setting::Arena,
+        Setting::Draft,
+        Setting::Daily,
+        Setting::Muffle,
+        Setting::ReducedFlashing,
+        Setting::MasterVolume,
+        Setting::EffectsVolume,
+        Setting::InterfaceVolume,
+        Setting::AmbienceVolume,
+        Setting::MusicVolume,
+    ];
+
+    fn label(&self, settings: &Settings, mixer: &Mixer, localizer: &Localizer) -> String {
+        let on_off = |value: bool| localizer.get(if value { "on" } else { "off" });
+        let labelled = |key: &str, value: String| localizer.format(key, &[("value", value)]);
+        match self {
+            Setting::Language => labelled("setting-language", localizer.get("language-name")),
+            Setting::SlashMode => match settings.slash_mode {
+                SlashMode::Tap => localizer.get("setting-slash-tap"),
+                SlashMode::Charge => localizer.get("setting-slash-charge"),
+            },
+            Setting::SlashBuffer => {
+                if settings.slash_buffer > 0.0 {
+                    let ms = (settings.slash_buffer * 1000.0).round();
+                    localizer.format("setting-slash-buffer", &[("ms", ms.to_string())])
+                } else {
+                    localizer.get("setting-slash-buffer-off")
+                }
+            }
+            Setting::BufferAim => match settings.buffer_aim {
+                BufferAim::Press => localizer.get("setting-buffer-aim-press"),
+                BufferAim::Release => localizer.get("setting-buffer-aim-release"),
+            },
+            Setting::Ricochet => labelled("setting-ricochet", on_off(settings.ricochet)),
+            Setting::Arena => match settings.arena {
+                0 => labelled("setting-arena", localizer.get("arena-open")),
+                arena => labelled("setting-arena", localizer.get(ARENAS[arena - 1].0)),
+            },
+            Setting::Draft => labelled("setting-draft", on_off(settings.draft)),
+            Setting::Daily => labelled("setting-daily", on_off(settings.daily)),
+            Setting::Muffle => labelled("setting-muffle", on_off(settings.muffle)),
+            Setting::ReducedFlashing => labelled(
+                "setting-reduced-flashing",
+                on_off(settings.reduced_flashing),
+            ),
+            Setting::MasterVolume => labelled("setting-master-volume", percent(mixer.master)),
+            Setting::EffectsVolume => labelled("setting-effects-volume", percent(mixer.effects)),
+            Setting::InterfaceVolume => {
+                labelled("setting-interface-volume", percent(mixer.interface))
+            }
+            Setting::AmbienceVolume => labelled("setting-ambience-volume", percent(mixer.ambience)),
+            Setting::MusicVolume => labelled("setting-music-volume", percent(mixer.music)),
+        }
+    }
+
+    fn toggle(&self, settings: &mut Settings, mixer: &mut Mixer) {
+        match self {
+            Setting::Language => settings.language = settings.language.next(),
+            Setting::SlashMode => {
+                settings.slash_mode = match settings.slash_mode {
+                    SlashMode::Tap => SlashMode::Charge,
+                    SlashMode::Charge => SlashMode::Tap,
+                }
+            }
+            Setting::SlashBuffer => {
+                let current = SLASH_BUFFERS
+                    .iter()
+                    .position(|buffer| *buffer == settings.slash_buffer)
+                    .unwrap_or(0);
+                settings.slash_buffer = SLASH_BUFFERS[(current + 1) % SLASH_BUFFERS.len()];
+            }
+            Setting::BufferAim => {
+                settings.buffer_aim = match settings.buffer_aim {
+                    BufferAim::Press => BufferAim::Release,
+                    BufferAim::Release => BufferAim::Press,
+                }
+            }
+            Setting::Ricochet => settings.ricochet = !settings.ricochet,
+            Setting::Arena => settings.arena = (settings.arena + 1) % (ARENAS.len() + 1),
+            Setting::Draft => settings.draft = !settings.draft,
+            Setting::Daily => settings.daily = !settings.daily,
+            Setting::Muffle => settings.muffle = !settings.muffle,
+            Setting::ReducedFlashing => settings.reduced_flashing = !settings.reduced_flashing,
+            Setting::MasterVolume => mixer.master = next_volume(mixer.master),
+            Setting::EffectsVolume => mixer.effects = next_volume(mixer.effects),
+            Setting::InterfaceVolume => mixer.interface = next_volume(mixer.interface),
+            Setting::AmbienceVolume => mixer.ambience = next_volume(mixer.ambience),
+            Setting::MusicVolume => mixer.music = next_volume(mixer.music),
+        }
+    }
+}
+
+/// Volumes go up in tenths and wrap back around to silent.
+fn next_volume(volume: f32) -> f32 {
+    ((volume * 10.0).round() + 1.0) % 11.0 / 10.0
+}
+
+fn percent(volume: f32) -> String {
+    format!("{}%", (volume * 100.0).round())
+}
+
+const SETTINGS_TOP: f32 = -10.0;
+const SETTINGS_SPACING: f32 = 32.0;
+
+#[derive(Resource)]
+struct SettingsCursor {
+    selected: usize,
+    stick_ready: bool,
+}
+
+impl Default for SettingsCursor {
+    fn default() -> SettingsCursor {
+        SettingsCursor {
+            selected: 0,
+            stick_ready: true,
+        }
+    }
+}
+
+#[derive(Component)]
+struct SettingsEntry {
+    index: usize,
+}
+
+#[derive(Component)]
+struct SettingsMenu;
+
+fn setup_settings_menu(mut commands: Commands, assets: Res<GameAssets>) {
+    let font = assets.font.clone();
+    for index in 0..Setting::ALL.len() {
+        commands.spawn((
+            SettingsMenu,
+            SettingsEntry { index },
+            Text2dBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 27.0,
+                        color: Color::srgb(2.5, 0.25, 0.25),
+                        ..default()
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                visibility: Visibility::Hidden,
+                transform: Transform::from_xyz(
+                    0.0,
+                    SETTINGS_TOP - index as f32 * SETTINGS_SPACING,
+                    10.0,
+                ),
+                ..default()
+            },
+        ));
+    }
+    commands.spawn((
+        SettingsMenu,
+        Localized {
+            key: "settings-hint",
+        },
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font,
+                    font_size: 25.0,
+                    color: Color::srgba(2.5, 0.25, 0.25, 0.5),
+                    ..default()
+                },
+            )
+            .with_justify(JustifyText::Center),
+            visibility: Visibility::Hidden,
+            transform: Transform::from_xyz(
+                0.0,
+                SETTINGS_TOP - Setting::ALL.len() as f32 * SETTINGS_SPACING,
+                10.0,
+            ),
+            ..default()
+        },
+    ));
+}
+
+fn show_settings(mut menu: Query<&mut Visibility, With<SettingsMenu>>) {
+    for mut visibility in menu.iter_mut() {
+        *visibility = Visibility::Visible;
+    }
+}
+
+fn hide_settings(mut menu: Query<&mut Visibility, With<SettingsMenu>>) {
+    for mut visibility in menu.iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+/// Up and down pick a line, hovering it with the mouse does too, and `Dodge` flips it. `Slash`
+/// is left alone so it keeps starting the game.
+fn navigate_settings(
+    global: Res<GameGlobal>,
+    action_state: Res<ActionState<PlayerAction>>,
+    mut cursor: ResMut<SettingsCursor>,
+    mut settings: ResMut<Settings>,
+    mut mixer: ResMut<Mixer>,
+    mut sound: EventWriter<Sound>,
+) {
+    let count = Setting::ALL.len();
+    let stick = action_state.clamped_axis_pair(&PlayerAction::Run).y;
+    if cursor.stick_ready && f32::abs(stick) > 0.5 {
+        cursor.stick_ready = false;
+        cursor.selected = if stick < 0.0 {
+            usize::min(cursor.selected + 1, count - 1)
+        } else {
+            cursor.selected.saturating_sub(1)
+        };
+    } else if f32::abs(stick) < 0.2 {
+        cursor.stick_ready = true;
+    }
+
+    if !global.gamepad && f32::abs(global.cursor_position.x) < 400.0 {
+        let row =
+            (SETTINGS_TOP + SETTINGS_SPACING / 2.0 - global.cursor_position.y) / SETTINGS_SPACING;
+        if row >= 0.0 && (row as usize) < count {
+            cursor.selected = row as usize;
+        }
+    }
+
+    if action_state.just_pressed(&PlayerAction::Dodge) {
+        Setting::ALL[cursor.selected].toggle(&mut settings, &mut mixer);
+        sound.send(Sound {
+            id: SoundId::Select,
+            position: Vec3::ZERO,
+            speed: 1.0,
+        });
+    }
+}
+
+fn update_settings_menu(
+    settings: Res<Settings>,
+    mixer: Res<Mixer>,
+    localizer: Localizer,
+    cursor: Res<SettingsCursor>,
+    mut entries: Query<(&SettingsEntry, &mut Text)>,
+) {
+    for (entry, mut text) in entries.iter_mut() {
+        localizer.set(
+            &mut text,
+            Setting::ALL[entry.index].label(&settings, &mixer, &localizer),
+        );
+        let alpha = if entry.index == cursor.selected {
+            1.0
+        } else {
+            0.4
+        };
+        text.sections[0].style.color.set_alpha(alpha);
+    }
+}
+
+fn apply_settings(settings: Res<Settings>, mut draft: ResMut<Draft>) {
+    if !settings.is_changed() {
+        return;
+    }
+    draft.enabled = settings.draft;
+    draft.daily = settings.daily;
+    info!("Settings applied");
+}
@@ -0,0 +1,415 @@
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use crate::bushido::assets::GameAssets;
+use crate::bushido::settings::Settings;
+use crate::bushido::spatial::listener_position;
+use crate::bushido::spatial::Spatial;
+use crate::bushido::spatial::SpatialAudio;
+use crate::bushido::spatial::SpatialControls;
+use crate::bushido::spatial::SpatialSound;
+use crate::bushido::Sound;
+use crate::GameGlobal;
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use rand::Rng;
+use std::sync::Arc;
+
+pub struct SoundPlugin;
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Mixer::default())
+            .add_systems(Update, (play_sounds, apply_mixer.after(play_sounds)));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SoundId {
+    Slash,
+    Hit,
+    Vrrp,
+    Unready,
+    Finish,
+    Hurt,
+    Dead,
+    Kill,
+    Noise,
+    Attack,
+    /// The Dark Presence is one second from taking the player.
+    Omen,
+    /// A parry that caught an attacker.
+    Parry,
+    /// Raising the guard, whether or not anything runs into it.
+    Stance,
+    /// Moving through and changing things in the menus.
+    Select,
+}
+
+/// How a sound is mixed whenever it plays.
+pub struct SoundDef {
+    /// Next to the executable's other assets, extension included.
+    pub file: &'static str,
+    pub volume: f32,
+    /// The playback speed is nudged up or down by up to this much.
+    pub pitch_variance: f32,
+    /// Spatial sounds are panned, attenuated and muffled by where they happen, see `SpatialAudio`.
+    /// The rest play at the same level wherever they happen.
+    pub spatial: bool,
+    /// Past this many at once, the oldest one is cut off to make room.
+    pub max_voices: usize,
+    pub bus: Bus,
+    /// Big moments that push every other sound down for a while.
+    pub ducks: bool,
+}
+
+impl SoundId {
+    pub const ALL: [SoundId; 14] = [
+        SoundId::Slash,
+        SoundId::Hit,
+        SoundId::Vrrp,
+        SoundId::Unready,
+        SoundId::Finish,
+        SoundId::Hurt,
+        SoundId::Dead,
+        SoundId::Kill,
+        SoundId::Noise,
+        SoundId::Attack,
+        SoundId::Omen,
+        SoundId::Parry,
+        SoundId::Stance,
+        SoundId::Select,
+    ];
+
+    pub fn def(&self) -> SoundDef {
+        match self {
+            SoundId::Slash => SoundDef {
+                file: "slash.mp3",
+                volume: 1.0,
+                pitch_variance: 0.1,
+                spatial: true,
+                max_voices: 4,
+                bus: Bus::Effects,
+                ducks: false,
+            },
+            SoundId::Hit => SoundDef {
+                file: "hit.mp3",
+                volume: 1.0,
+                pitch_variance: 0.1,
+                spatial: true,
+                max_voices: 6,
+                bus: Bus::Effects,
+                ducks: false,
+            },
+            SoundId::Vrrp => SoundDef {
+                file: "vrrp.mp3",
+                volume: 0.8,
+                pitch_variance: 0.05,
+                spatial: true,
+                max_voices: 2,
+                bus: Bus::Effects,
+                ducks: false,
+            },
+            SoundId::Unready => SoundDef {
+                file: "unready.mp3",
+                volume: 1.0,
+                pitch_variance: 0.0,
+                spatial: false,
+                max_voices: 2,
+                bus: Bus::Effects,
+                ducks: false,
+            },
+            SoundId::Finish => SoundDef {
+                file: "finish.mp3",
+                volume: 1.0,
+                pitch_variance: 0.1,
+                spatial: true,
+                max_voices: 2,
+                bus: Bus::Effects,
+                ducks: false,
+            },
+            SoundId::Hurt => SoundDef {
+                file: "hurt.mp3",
+                volume: 1.0,
+                pitch_variance: 0.05,
+                spatial: true,
+                max_voices: 2,
+                bus: Bus::Effects,
+                ducks: false,
+            },
+            SoundId::Dead => SoundDef {
+                file: "dead.mp3",
+                volume: 1.0,
+                pitch_variance: 0.0,
+                spatial: false,
+                max_voices: 1,
+                bus: Bus::Effects,
+                ducks: true,
+            },
+            SoundId::Kill => SoundDef {
+                file: "kill.mp3",
+                volume: 0.8,
+                pitch_variance: 0.15,
+                spatial: true,
+                max_voices: 4,
+                bus: Bus::Effects,
+                ducks: false,
+            },
+            SoundId::Noise => SoundDef {
+                file: "noise.mp3",
+                volume: 1.0,
+                pitch_variance: 0.0,
+                spatial: false,
+                max_voices: 1,
+                bus: Bus::Ambience,
+                ducks: false,
+            },
+            SoundId::Attack => SoundDef {
+                file: "attack.mp3",
+                volume: 1.0,
+                pitch_variance: 0.0,
+                spatial: false,
+                max_voices: 1,
+                bus: Bus::Effects,
+                ducks: true,
+            },
+            SoundId::Omen => SoundDef {
+                file: "omen.wav",
+                volume: 1.0,
+                pitch_variance: 0.0,
+                spatial: false,
+                max_voices: 1,
+                bus: Bus::Effects,
+                ducks: false,
+            },
+            SoundId::Parry => SoundDef {
+                file: "parry.wav",
+                volume: 0.9,
+                pitch_variance: 0.05,
+                spatial: true,
+                max_voices: 2,
+                bus: Bus::Effects,
+                ducks: false,
+            },
+            SoundId::Stance => SoundDef {
+                file: "stance.wav",
+                volume: 0.4,
+                pitch_variance: 0.1,
+                spatial: true,
+                max_voices: 2,
+                bus: Bus::Effects,
+                ducks: false,
+            },
+            SoundId::Select => SoundDef {
+                file: "select.wav",
+                volume: 0.6,
+                pitch_variance: 0.0,
+                spatial: false,
+                max_voices: 2,
+                bus: Bus::Interface,
+                ducks: false,
+            },
+        }
+    }
+}
+
+/// Where a sound's volume is set, on top of the master volume.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Bus {
+    Effects,
+    /// Menu blips.
+    Interface,
+    /// Loops that sit under everything, like the static outside the window.
+    Ambience,
+    Music,
+}
+
+/// How far ducked buses drop.
+pub const DUCK_LEVEL: f32 = 0.3;
+/// How long ducking holds after the sound that started it.
+pub const DUCK_HOLD: f32 = 2.0;
+/// How long it takes to drop, and to come back up, in seconds.
+pub const DUCK_ATTACK: f32 = 0.1;
+pub const DUCK_RELEASE: f32 = 1.0;
+
+/// Volumes for every bus, between 0 and 1, set from the options menu.
+#[derive(Resource)]
+pub struct Mixer {
+    pub master: f32,
+    pub effects: f32,
+    pub interface: f32,
+    pub ambience: f32,
+    pub music: f32,
+    /// How far into a duck the mix is, between 0 and 1.
+    duck: f32,
+    hold: Timer,
+}
+
+impl Default for Mixer {
+    fn default() -> Mixer {
+        let mut hold = Timer::from_seconds(DUCK_HOLD, TimerMode::Once);
+        hold.tick(hold.duration());
+        Mixer {
+            master: 1.0,
+            effects: 1.0,
+            interface: 1.0,
+            ambience: 1.0,
+            music: 0.7,
+            duck: 0.0,
+            hold,
+        }
+    }
+}
+
+impl Mixer {
+    /// The gain for a sound on `bus`. Sounds that started a duck don't get ducked themselves.
+    pub fn gain(&self, bus: Bus, ducks: bool) -> f32 {
+        let level = match bus {
+            Bus::Effects => self.effects,
+            Bus::Interface => self.interface,
+            Bus::Ambience => self.ambience,
+            Bus::Music => self.music,
+        };
+        let duck = if ducks {
+            1.0
+        } else {
+            1.0 - self.duck * (1.0 - DUCK_LEVEL)
+        };
+        self.master * level * duck
+    }
+
+    pub fn duck(&mut self) {
+        self.hold.reset();
+    }
+}
+
+/// A sound's own volume and the bus it plays through. The mixer sets the actual volume from it
+/// every frame, so systems that fade a sound change `volume` here instead of on the sink.
+#[derive(Component)]
+pub struct Mixed {
+    pub bus: Bus,
+    pub volume: f32,
+    pub ducks: bool,
+}
+
+impl Mixed {
+    pub fn of(id: SoundId) -> Mixed {
+        let def = id.def();
+        Mixed {
+            bus: def.bus,
+            volume: def.volume,
+            ducks: def.ducks,
+        }
+    }
+
+    pub fn level(&self, mixer: &Mixer) -> f32 {
+        self.volume * mixer.gain(self.bus, self.ducks)
+    }
+}
+
+/// A playing sound, so later ones of the same kind can steal it.
+#[derive(Component)]
+struct Voice {
+    id: SoundId,
+    started: f32,
+}
+
+fn play_sounds(
+    time: Res<Time>,
+    mut global: ResMut<GameGlobal>,
+    mut mixer: ResMut<Mixer>,
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    settings: Res<Settings>,
+    spatial: Res<SpatialAudio>,
+    audio: Res<Assets<AudioSource>>,
+    mut spatial_sounds: ResMut<Assets<SpatialSound>>,
+    mut new_sounds: EventReader<Sound>,
+    voices: Query<(Entity, &Voice)>,
+    listeners: Query<&GlobalTransform, With<SpatialListener>>,
+) {
+    if new_sounds.is_empty() {
+        return;
+    }
+    let mut playing: HashMap<SoundId, Vec<(f32, Entity)>> = HashMap::default();
+    for (entity, voice) in voices.iter() {
+        playing
+            .entry(voice.id)
+            .or_default()
+            .push((voice.started, entity));
+    }
+    for voices in playing.values_mut() {
+        voices.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    }
+
+    for sound in new_sounds.read() {
+        let def = sound.id.def();
+        let jitter = (global.rand.gen::<f32>() * 2.0 - 1.0) * def.pitch_variance;
+        if def.ducks {
+            mixer.duck();
+        }
+        let mixed = Mixed::of(sound.id);
+        let playback = PlaybackSettings::DESPAWN
+            .with_volume(Volume::new(mixed.level(&mixer)))
+            .with_speed(sound.speed + jitter);
+        let mut voice = commands.spawn((
+            Voice {
+                id: sound.id,
+                started: time.elapsed_seconds(),
+            },
+            mixed,
+            SpatialBundle {
+                transform: Transform::from_xyz(sound.position.x, sound.position.y, 0.0),
+                ..default()
+            },
+        ));
+        let handle = assets.sound(sound.id);
+        match audio.get(&handle).filter(|_| def.spatial) {
+            Some(source) => {
+                let (left, right, cutoff) = spatial.controls(
+                    &global,
+                    settings.muffle,
+                    listener_position(&listeners),
+                    sound.position.truncate(),
+                );
+                let controls = Arc::new(SpatialControls::new(left, right, cutoff));
+                voice.insert((
+                    Spatial {
+                        controls: controls.clone(),
+                    },
+                    AudioSourceBundle {
+                        source: spatial_sounds.add(SpatialSound {
+                            audio: source.clone(),
+                            controls,
+                        }),
+                        settings: playback,
+                    },
+                ));
+            }
+            None => {
+                voice.insert(AudioBundle {
+                    source: handle,
+                    settings: playback,
+                });
+            }
+        }
+        let voice = voice.id();
+        let voices = playing.entry(sound.id).or_default();
+        voices.push((time.elapsed_seconds(), voice));
+        while voices.len() > def.max_voices {
+            let (_, oldest) = voices.remove(0);
+            commands.entity(oldest).despawn_recursive();
+        }
+    }
+}
+
+fn apply_mixer(time: Res<Time>, mut mixer: ResMut<Mixer>, sounds: Query<(&Mixed, &AudioSink)>) {
+    mixer.hold.tick(time.delta());
+    let duck = mixer.duck;
+    mixer.duck = if mixer.hold.finished() {
+        f32::max(duck - time.delta_seconds() / DUCK_RELEASE, 0.0)
+    } else {
+        f32::min(duck + time.delta_seconds() / DUCK_ATTACK, 1.0)
+    };
+    for (mixed, sink) in sounds.iter() {
+        sink.set_volume(mixed.level(&mixer));
+    }
+}
@@ -0,0 +1,459 @@
+#![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_variables))]
+use crate::bushido::settings::Settings;
+use crate::GameGlobal;
+use bevy::audio::AddAudioSource;
+use bevy::audio::Decodable;
+use bevy::audio::Sample;
+use bevy::audio::Source;
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use std::f32::consts::PI;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// Cutoff for sounds from outside the inner window, in hertz.
+pub const MUFFLE_CUTOFF: f32 = 900.0;
+/// Anything at or above this leaves the sound untouched.
+pub const OPEN_CUTOFF: f32 = 20000.0;
+/// How quickly gains and cutoff follow their targets, per sample, so moving doesn't click.
+const SMOOTHING: f32 = 0.002;
+
+pub struct SpatialAudioPlugin;
+impl Plugin for SpatialAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<SpatialSound>()
+            .insert_resource(SpatialAudio::default())
+            .add_systems(Update, place_voices);
+    }
+}
+
+/// How loud a sound is by its distance from the listener.
+#[derive(Clone, Copy, Debug)]
+pub struct Attenuation {
+    /// Closer than this plays at full volume.
+    pub reference: f32,
+    /// How quickly it falls off past `reference`. 1 halves the volume at twice the distance.
+    pub rolloff: f32,
+    /// The quietest a sound gets, however far away it is.
+    pub floor: f32,
+}
+
+impl Attenuation {
+    pub fn gain(&self, distance: f32) -> f32 {
+        if distance <= self.reference {
+            return 1.0;
+        }
+        let gain = self.reference / (self.reference + self.rolloff * (distance - self.reference));
+        f32::max(gain, self.floor)
+    }
+
+    fn scaled(&self, scale: f32) -> Attenuation {
+        Attenuation {
+            reference: self.reference * scale,
+            ..*self
+        }
+    }
+}
+
+/// Tuning for positioned sounds. Everything is in world units, measured from the listener.
+#[derive(Resource)]
+pub struct SpatialAudio {
+    pub attenuation: Attenuation,
+    /// How far to the side a sound has to be to come out of one speaker only.
+    pub width: f32,
+    /// `attenuation` and `width` are stretched by this while the window is expanded, so the far
+    /// side of the monitor stays audible and panned across the whole stereo field.
+    pub expanded_scale: f32,
+}
+
+impl Default for SpatialAudio {
+    fn default() -> SpatialAudio {
+        SpatialAudio {
+            attenuation: Attenuation {
+                reference: 150.0,
+                rolloff: 0.6,
+                floor: 0.15,
+            },
+            width: 700.0,
+            expanded_scale: 2.0,
+        }
+    }
+}
+
+impl SpatialAudio {
+    /// Left and right gains for a sound `offset` away from the listener, panned at equal power.
+    pub fn gains(&self, offset: Vec2, expanded: bool) -> (f32, f32) {
+        let scale = if expanded { self.expanded_scale } else { 1.0 };
+        let gain = self.attenuation.scaled(scale).gain(offset.length());
+        let pan = (offset.x / (self.width * scale)).clamp(-1.0, 1.0);
+        let angle = (pan + 1.0) * PI / 4.0;
+        (gain * f32::cos(angle), gain * f32::sin(angle))
+    }
+
+    /// Gains and cutoff for a sound at `position`, as heard from `listener`.
+    pub fn controls(
+        &self,
+        global: &GameGlobal,
+        muffle: bool,
+        listener: Vec2,
+        position: Vec2,
+    ) -> (f32, f32, f32) {
+        let (left, right) = self.gains(position - listener, global.expanded);
+        let outside = f32::abs(position.x) > global.inner_world_size.x / 2.0
+            || f32::abs(position.y) > global.inner_world_size.y / 2.0;
+        let cutoff = if muffle && outside {
+            MUFFLE_CUTOFF
+        } else {
+            OPEN_CUTOFF
+        };
+        (left, right, cutoff)
+    }
+}
+
+/// Targets shared between the game and the audio thread.
+#[derive(Default)]
+pub struct SpatialControls {
+    left: AtomicU32,
+    right: AtomicU32,
+    cutoff: AtomicU32,
+}
+
+impl SpatialControls {
+    pub fn new(left: f32, right: f32, cutoff: f32) -> SpatialControls {
+        let controls = SpatialControls::default();
+        controls.set(left, right, cutoff);
+        controls
+    }
+
+    pub fn set(&self, left: f32, right: f32, cutoff: f32) {
+        self.left.store(left.to_bits(), Ordering::Relaxed);
+        self.right.store(right.to_bits(), Ordering::Relaxed);
+        self.cutoff.store(cutoff.to_bits(), Ordering::Relaxed);
+    }
+
+    fn get(&self) -> (f32, f32, f32) {
+        (
+            f32::from_bits(self.left.load(Ordering::Relaxed)),
+            f32::from_bits(self.right.load(Ordering::Relaxed)),
+            f32::from_bits(self.cutoff.load(Ordering::Relaxed)),
+        )
+    }
+}
+
+/// The per-sample part: gains each side and runs a one-pole low-pass. Works on plain floats so it
+/// can run without an audio device.
+pub struct Spatializer {
+    controls: Arc<SpatialControls>,
+    sample_rate: f32,
+    left: f32,
+    right: f32,
+    cutoff: f32,
+    filtered: f32,
+}
+
+impl Spatializer {
+    pub fn new(controls: Arc<SpatialControls>, sample_rate: u32) -> Spatializer {
+        let (left, right, cutoff) = controls.get();
+        Spatializer {
+            controls,
+            sample_rate: sample_rate as f32,
+            left,
+            right,
+            cutoff,
+            filtered: 0.0,
+        }
+    }
+
+    /// Takes one mono sample and returns the left and right ones.
+    pub fn process(&mut self, sample: f32) -> (f32, f32) {
+        let (left, right, cutoff) = self.controls.get();
+        self.left += (left - self.left) * SMOOTHING;
+        self.right += (right - self.right) * SMOOTHING;
+        self.cutoff += (cutoff - self.cutoff) * SMOOTHING;
+
+        let filtered;
+        if self.cutoff >= OPEN_CUTOFF {
+            filtered = sample;
+        } else {
+            let alpha = 1.0 - f32::exp(-2.0 * PI * self.cutoff / self.sample_rate);
+            filtered = self.filtered + alpha * (sample - self.filtered);
+        }
+        self.filtered = filtered;
+        (filtered * self.left, filtered * self.right)
+    }
+}
+
+/// Wraps a decoder, folds it down to mono and spreads it back out to stereo through a
+/// `Spatializer`.
+pub struct Spatialized<D> {
+    input: D,
+    channels: u16,
+    spatializer: Spatializer,
+    /// The right sample waiting to go out after the left one.
+    pending: Option<f32>,
+}
+
+impl<D> Spatialized<D>
+where
+    D: Source,
+    D::Item: Sample,
+{
+    pub fn new(input: D, controls: Arc<SpatialControls>) -> Spatialized<D> {
+        let channels = u16::max(input.channels(), 1);
+        let spatializer = Spatializer::new(controls, input.sample_rate());
+        Spatialized {
+            input,
+            channels,
+            spatializer,
+            pending: None,
+        }
+    }
+}
+
+impl<D> Iterator for Spatialized<D>
+where
+    D: Source,
+    D::Item: Sample,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if let Some(right) = self.pending.take() {
+            return Some(right);
+        }
+        let mut sum = 0.0;
+        for _ in 0..self.channels {
+            sum += self.input.next()?.to_f32();
+        }
+        let (left, right) = self.spatializer.process(sum / self.channels as f32);
+        self.pending = Some(right);
+        Some(left)
+    }
+}
+
+impl<D> Source for Spatialized<D>
+where
+    D: Source,
+    D::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+/// One playing positioned sound: the file and the controls steering it.
+#[derive(Asset, TypePath)]
+pub struct SpatialSound {
+    pub audio: AudioSource,
+    pub controls: Arc<SpatialControls>,
+}
+
+impl Decodable for SpatialSound {
+    type DecoderItem = f32;
+    type Decoder = Spatialized<<AudioSource as Decodable>::Decoder>;
+
+    fn decoder(&self) -> Self::Decoder {
+        Spatialized::new(self.audio.decoder(), self.controls.clone())
+    }
+}
+
+/// Marks a voice as positioned, with the controls of its `SpatialSound`.
+#[derive(Component)]
+pub struct Spatial {
+    pub controls: Arc<SpatialControls>,
+}
+
+/// Where the player hears from, or the middle of the screen when there's no player.
+pub fn listener_position(listeners: &Query<&GlobalTransform, With<SpatialListener>>) -> Vec2 {
+    listeners
+        .get_single()
+        .map_or(Vec2::ZERO, |transform| transform.translation().truncate())
+}
+
+/// Keeps every positioned voice panned and attenuated as the listener moves.
+fn place_voices(
+    global: Res<GameGlobal>,
+    settings: Res<Settings>,
+    spatial: Res<SpatialAudio>,
+    listeners: Query<&GlobalTransform, With<SpatialListener>>,
+    voices: Query<(&Transform, &Spatial)>,
+) {
+    let listener = listener_position(&listeners);
+    for (transform, voice) in voices.iter() {
+        let (left, right, cutoff) = spatial.controls(
+            &global,
+            settings.muffle,
+            listener,
+            transform.translation.truncate(),
+        );
+        voice.controls.set(left, right, cutoff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// Plain interleaved samples, standing in for a decoded file.
+    struct Samples {
+        samples: std::vec::IntoIter<f32>,
+        channels: u16,
+    }
+
+    impl Iterator for Samples {
+        type Item = f32;
+
+        fn next(&mut self) -> Option<f32> {
+            self.samples.next()
+        }
+    }
+
+    impl Source for Samples {
+        fn current_frame_len(&self) -> Option<usize> {
+            None
+        }
+
+        fn channels(&self) -> u16 {
+            self.channels
+        }
+
+        fn sample_rate(&self) -> u32 {
+            SAMPLE_RATE
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            None
+        }
+    }
+
+    fn spatializer_with(left: f32, right: f32, cutoff: f32) -> Spatializer {
+        Spatializer::new(
+            Arc::new(SpatialControls::new(left, right, cutoff)),
+            SAMPLE_RATE,
+        )
+    }
+
+    #[test]
+    fn attenuation_curve() {
+        let attenuation = Attenuation {
+            reference: 100.0,
+            rolloff: 1.0,
+            floor: 0.1,
+        };
+        assert_eq!(attenuation.gain(50.0), 1.0);
+        assert_eq!(attenuation.gain(100.0), 1.0);
+        assert!((attenuation.gain(200.0) - 0.5).abs() < EPSILON);
+        assert!(attenuation.gain(300.0) < attenuation.gain(200.0));
+        assert_eq!(attenuation.gain(10000.0), 0.1);
+    }
+
+    #[test]
+    fn equal_power_panning() {
+        let spatial = SpatialAudio::default();
+        let (left, right) = spatial.gains(Vec2::ZERO, false);
+        assert!((left - right).abs() < EPSILON);
+        assert!((left * left + right * right - 1.0).abs() < EPSILON);
+
+        let edge = Vec2::new(spatial.width, 0.0);
+        let gain = spatial.attenuation.gain(spatial.width);
+        let (left, right) = spatial.gains(edge, false);
+        assert!(left.abs() < EPSILON);
+        assert!((right - gain).abs() < EPSILON);
+        let (left, right) = spatial.gains(-edge, false);
+        assert!((left - gain).abs() < EPSILON);
+        assert!(right.abs() < EPSILON);
+
+        for x in [-300.0, -50.0, 120.0, 500.0] {
+            let offset = Vec2::new(x, 0.0);
+            let gain = spatial.attenuation.gain(offset.length());
+            let (left, right) = spatial.gains(offset, false);
+            assert!((left * left + right * right - gain * gain).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn expanded_spreads_wider() {
+        let spatial = SpatialAudio::default();
+        let edge = Vec2::new(spatial.width, 0.0);
+        let (left, right) = spatial.gains(edge, true);
+        assert!(left > EPSILON, "still heard on the left once expanded");
+        assert!(right > left);
+        let (_, near_right) = spatial.gains(edge, false);
+        assert!(left * left + right * right > near_right * near_right);
+
+        let far_edge = edge * spatial.expanded_scale;
+        let (left, _) = spatial.gains(far_edge, true);
+        assert!(left.abs() < EPSILON);
+    }
+
+    #[test]
+    fn open_cutoff_leaves_samples_alone() {
+        let mut spatializer = spatializer_with(1.0, 1.0, OPEN_CUTOFF);
+        for sample in [0.0, 1.0, -1.0, 0.25, -0.7, 1.0, -1.0] {
+            assert_eq!(spatializer.process(sample), (sample, sample));
+        }
+    }
+
+    #[test]
+    fn muffle_cuts_high_frequencies() {
+        let mut spatializer = spatializer_with(1.0, 1.0, MUFFLE_CUTOFF);
+        let mut loudest: f32 = 0.0;
+        for index in 0..2000 {
+            // Alternating signs is the highest frequency there is.
+            let sample = if index % 2 == 0 { 1.0 } else { -1.0 };
+            let (left, _) = spatializer.process(sample);
+            if index > 1000 {
+                loudest = loudest.max(left.abs());
+            }
+        }
+        assert!(loudest < 0.1, "{} got through", loudest);
+
+        let mut spatializer = spatializer_with(1.0, 1.0, MUFFLE_CUTOFF);
+        let mut left = 0.0;
+        for _ in 0..2000 {
+            left = spatializer.process(1.0).0;
+        }
+        assert!((left - 1.0).abs() < 0.01, "steady signals pass");
+    }
+
+    #[test]
+    fn folds_stereo_to_mono_and_interleaves() {
+        let input = Samples {
+            samples: vec![1.0, 0.0, 0.5, 0.5, -1.0, 0.0].into_iter(),
+            channels: 2,
+        };
+        let controls = Arc::new(SpatialControls::new(1.0, 0.5, OPEN_CUTOFF));
+        let spatialized = Spatialized::new(input, controls);
+        assert_eq!(spatialized.channels(), 2);
+        assert_eq!(spatialized.sample_rate(), SAMPLE_RATE);
+        let output = spatialized.collect::<Vec<f32>>();
+        assert_eq!(output, vec![0.5, 0.25, 0.5, 0.25, -0.5, -0.25]);
+    }
+
+    #[test]
+    fn mono_comes_out_on_both_sides() {
+        let input = Samples {
+            samples: vec![0.5, -0.5].into_iter(),
+            channels: 1,
+        };
+        let controls = Arc::new(SpatialControls::new(1.0, 1.0, OPEN_CUTOFF));
+        let output = Spatialized::new(input, controls).collect::<Vec<f32>>();
+        assert_eq!(output, vec![0.5, 0.5, -0.5, -0.5]);
+    }
+}